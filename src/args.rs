@@ -0,0 +1,24 @@
+//Command line helpers shared by the game and the simulator
+
+//Gets the value given after a command line flag, like the file in "--replay game.json"
+pub fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    let position = args.iter().position(|arg| arg == flag)?;
+    args.get(position + 1).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flag_value() {
+        let args: Vec<String> = ["game", "--hours", "10", "--seed"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(flag_value(&args, "--hours"), Some(String::from("10")));
+        //A flag at the end has nothing after it
+        assert_eq!(flag_value(&args, "--seed"), None);
+        assert_eq!(flag_value(&args, "--clicks"), None);
+    }
+}
@@ -0,0 +1,60 @@
+//Plays the game with no window to see how fast a purchase strategy gets through it
+//Usage: simulator [--strategy cheapest|payback|random|all] [--hours <n>] [--clicks <per second>] [--seed <n>]
+use spice_harvesting::args::flag_value;
+use spice_harvesting::simulator::simulate;
+use spice_harvesting::strategy::Strategy;
+
+const STRATEGIES: [&str; 3] = ["cheapest", "payback", "random"];
+
+//Stops the simulator when a flag's value can't be used
+fn bad_flag(flag: &str, expected: &str) -> ! {
+    println!("{} must be {}", flag, expected);
+    std::process::exit(1);
+}
+
+//Reads a number flag, exiting with a message if it isn't a usable number
+fn number_flag(args: &[String], flag: &str, default: f64) -> f64 {
+    match flag_value(args, flag) {
+        Some(value) => match value.parse::<f64>() {
+            Ok(number) if number.is_finite() && number >= 0.0 => number,
+            _ => bad_flag(flag, "a number, 0 or more"),
+        },
+        None => default,
+    }
+}
+
+//Reads the seed, which has to be a whole number so every seed gives its own run
+fn seed_flag(args: &[String], default: u64) -> u64 {
+    match flag_value(args, "--seed") {
+        Some(value) => value
+            .parse::<u64>()
+            .unwrap_or_else(|_| bad_flag("--seed", "a whole number, 0 or more")),
+        None => default,
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let hours = number_flag(&args, "--hours", 10.0);
+    let clicks = number_flag(&args, "--clicks", 5.0);
+    let seed = seed_flag(&args, 1);
+    let strategy = flag_value(&args, "--strategy");
+    let names = match strategy.as_deref() {
+        None | Some("all") => STRATEGIES.to_vec(),
+        Some(name) => vec![name],
+    };
+
+    for name in names {
+        let Some(mut strategy) = Strategy::parse(name, seed) else {
+            println!(
+                "Unknown strategy {}, pick one of {} or all",
+                name,
+                STRATEGIES.join(", ")
+            );
+            std::process::exit(1);
+        };
+        //Every strategy gets the same seed so they face the same events and weather
+        print!("{}", simulate(&mut strategy, hours, clicks, seed).summary());
+        println!();
+    }
+}
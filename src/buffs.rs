@@ -0,0 +1,304 @@
+use serde::{Deserialize, Serialize};
+
+//What a buff changes
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum BuffTarget {
+    Sps,
+    Spc,
+    //Output of a single building, by item name
+    Building(String),
+}
+
+//How a buff's value is applied
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum BuffOp {
+    //Added to the base before any multipliers
+    Add,
+    #[default]
+    Multiply,
+}
+
+//What happens when a buff is added while one with the same name is running
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum Stacking {
+    //Old one is replaced and the timer starts over
+    #[default]
+    Refresh,
+    //Timer is extended by the new duration, value stays the same
+    Extend,
+    //Both run side by side, up to this many copies
+    Stack(usize),
+    //Whichever has the bigger effect is kept, timer starts over if the new one wins
+    Strongest,
+}
+
+//A temporary change to production or clicking, a debuff is just a buff that makes things worse
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Buff {
+    pub name: String,
+    pub target: BuffTarget,
+    #[serde(default)]
+    pub op: BuffOp,
+    //Amount added or multiplied by, older saves called this multiplier
+    #[serde(alias = "multiplier")]
+    pub value: f64,
+    //Seconds left before it wears off
+    pub time_left: f64,
+    //Seconds it lasted when it started, used to draw the countdown
+    #[serde(default)]
+    pub duration: f64,
+}
+
+impl Buff {
+    //Create a buff that multiplies its target
+    pub fn multiply(name: &str, target: BuffTarget, value: f64, duration: f64) -> Buff {
+        Buff {
+            name: name.to_string(),
+            target,
+            op: BuffOp::Multiply,
+            value,
+            time_left: duration,
+            duration,
+        }
+    }
+
+    //Whether this makes things worse instead of better
+    pub fn is_debuff(&self) -> bool {
+        match self.op {
+            BuffOp::Add => self.value < 0.0,
+            BuffOp::Multiply => self.value < 1.0,
+        }
+    }
+
+    //Gets how much of the buff's time is left, 1 when it just started
+    pub fn fraction_left(&self) -> f64 {
+        if self.duration > 0.0 {
+            (self.time_left / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+
+    //Short description of the effect, like "x7 spice per second"
+    pub fn describe(&self) -> String {
+        let amount = match self.op {
+            BuffOp::Add if self.value < 0.0 => format!("{}", self.value),
+            BuffOp::Add => format!("+{}", self.value),
+            BuffOp::Multiply => format!("x{}", self.value),
+        };
+        let target = match &self.target {
+            BuffTarget::Sps => String::from("spice per second"),
+            BuffTarget::Spc => String::from("spice per click"),
+            BuffTarget::Building(name) => format!("{} output", name),
+        };
+        format!("{} {}", amount, target)
+    }
+
+    //How strong the buff is, used by the Strongest stacking rule
+    fn strength(&self) -> f64 {
+        match self.op {
+            BuffOp::Add => self.value,
+            //Compared on the same scale as an add so x2 beats x1.5
+            BuffOp::Multiply => self.value - 1.0,
+        }
+    }
+}
+
+//Every buff and debuff currently running, saved with their remaining time
+#[derive(Serialize, Deserialize, Default)]
+pub struct Buffs {
+    active: Vec<Buff>,
+}
+
+impl Buffs {
+    //Gets the running buffs
+    pub fn get_active(&self) -> &[Buff] {
+        &self.active
+    }
+
+    //Starts a buff, following the stacking rule if one with the same name is running
+    pub fn add(&mut self, buff: Buff, stacking: Stacking) {
+        let same_name = |other: &&mut Buff| other.name == buff.name;
+        match stacking {
+            Stacking::Refresh => {
+                self.active.retain(|other| other.name != buff.name);
+                self.active.push(buff);
+            }
+            Stacking::Extend => match self.active.iter_mut().find(same_name) {
+                Some(existing) => {
+                    existing.time_left += buff.time_left;
+                    existing.duration = existing.time_left.max(existing.duration);
+                }
+                None => self.active.push(buff),
+            },
+            Stacking::Stack(max) => {
+                let count = self.active.iter().filter(|o| o.name == buff.name).count();
+                //At the limit the copy closest to running out makes room
+                if count >= max.max(1) {
+                    let oldest = self
+                        .active
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, other)| other.name == buff.name)
+                        .min_by(|(_, a), (_, b)| a.time_left.total_cmp(&b.time_left))
+                        .map(|(i, _)| i);
+                    if let Some(i) = oldest {
+                        self.active.remove(i);
+                    }
+                }
+                self.active.push(buff);
+            }
+            Stacking::Strongest => match self.active.iter_mut().find(same_name) {
+                Some(existing) => {
+                    if buff.strength() >= existing.strength() {
+                        *existing = buff;
+                    }
+                }
+                None => self.active.push(buff),
+            },
+        }
+    }
+
+    //Counts down every buff and removes the ones that ran out
+    pub fn advance(&mut self, dt: f64) {
+        for buff in self.active.iter_mut() {
+            buff.time_left -= dt;
+        }
+        self.active.retain(|buff| buff.time_left > 0.0);
+    }
+
+    //Gets the seconds until the next buff runs out, None if nothing is running
+    pub fn next_expiry(&self) -> Option<f64> {
+        self.active
+            .iter()
+            .map(|buff| buff.time_left)
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    //Applies every buff on a target to a base value
+    //Adds go first, then everything is multiplied, so a x2 doubles a +5 too
+    pub fn apply(&self, target: &BuffTarget, base: f64) -> f64 {
+        let mut added = base;
+        let mut multiplier = 1.0;
+        for buff in self.active.iter().filter(|buff| &buff.target == target) {
+            match buff.op {
+                BuffOp::Add => added += buff.value,
+                BuffOp::Multiply => multiplier *= buff.value,
+            }
+        }
+        (added * multiplier).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_buff(name: &str, target: BuffTarget, value: f64, duration: f64) -> Buff {
+        Buff {
+            op: BuffOp::Add,
+            ..Buff::multiply(name, target, value, duration)
+        }
+    }
+
+    #[test]
+    fn test_apply_and_expiry() {
+        let mut buffs = Buffs::default();
+        assert_eq!(buffs.apply(&BuffTarget::Sps, 3.0), 3.0);
+        buffs.add(
+            Buff::multiply("Frenzy", BuffTarget::Sps, 7.0, 10.0),
+            Stacking::Refresh,
+        );
+        buffs.add(
+            add_buff("Bonus", BuffTarget::Sps, 2.0, 20.0),
+            Stacking::Refresh,
+        );
+        buffs.add(
+            Buff::multiply("Click Frenzy", BuffTarget::Spc, 777.0, 5.0),
+            Stacking::Refresh,
+        );
+        //(3 + 2) * 7
+        assert_eq!(buffs.apply(&BuffTarget::Sps, 3.0), 35.0);
+        assert_eq!(buffs.apply(&BuffTarget::Spc, 1.0), 777.0);
+        assert_eq!(buffs.next_expiry(), Some(5.0));
+        buffs.advance(10.0);
+        assert_eq!(buffs.apply(&BuffTarget::Sps, 3.0), 5.0);
+        assert_eq!(buffs.apply(&BuffTarget::Spc, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_building_target_and_debuffs() {
+        let mut buffs = Buffs::default();
+        let fremen = BuffTarget::Building(String::from("Fremen"));
+        buffs.add(
+            Buff::multiply("Storm", fremen.clone(), 0.5, 10.0),
+            Stacking::Refresh,
+        );
+        assert!(buffs.get_active()[0].is_debuff());
+        assert_eq!(buffs.apply(&fremen, 10.0), 5.0);
+        //Other buildings aren't touched
+        let sietch = BuffTarget::Building(String::from("Sietch"));
+        assert_eq!(buffs.apply(&sietch, 10.0), 10.0);
+        //Debuffs can't push production below zero
+        buffs.add(
+            add_buff("Drain", fremen.clone(), -100.0, 10.0),
+            Stacking::Refresh,
+        );
+        assert_eq!(buffs.apply(&fremen, 10.0), 0.0);
+    }
+
+    #[test]
+    fn test_stacking_rules() {
+        let mut buffs = Buffs::default();
+        let frenzy = |time| Buff::multiply("Frenzy", BuffTarget::Sps, 2.0, time);
+
+        buffs.add(frenzy(10.0), Stacking::Refresh);
+        buffs.advance(8.0);
+        buffs.add(frenzy(10.0), Stacking::Refresh);
+        assert_eq!(buffs.get_active().len(), 1);
+        assert_eq!(buffs.get_active()[0].time_left, 10.0);
+
+        buffs.add(frenzy(5.0), Stacking::Extend);
+        assert_eq!(buffs.get_active().len(), 1);
+        assert_eq!(buffs.get_active()[0].time_left, 15.0);
+
+        buffs.add(frenzy(20.0), Stacking::Stack(2));
+        buffs.add(frenzy(30.0), Stacking::Stack(2));
+        //Limit of 2 pushed out the one closest to running out
+        assert_eq!(buffs.get_active().len(), 2);
+        assert_eq!(buffs.apply(&BuffTarget::Sps, 1.0), 4.0);
+        assert_eq!(buffs.next_expiry(), Some(20.0));
+    }
+
+    #[test]
+    fn test_strongest_wins() {
+        let mut buffs = Buffs::default();
+        buffs.add(
+            Buff::multiply("Blessing", BuffTarget::Spc, 3.0, 10.0),
+            Stacking::Strongest,
+        );
+        buffs.add(
+            Buff::multiply("Blessing", BuffTarget::Spc, 2.0, 60.0),
+            Stacking::Strongest,
+        );
+        assert_eq!(buffs.apply(&BuffTarget::Spc, 1.0), 3.0);
+        assert_eq!(buffs.get_active()[0].time_left, 10.0);
+        buffs.add(
+            Buff::multiply("Blessing", BuffTarget::Spc, 5.0, 30.0),
+            Stacking::Strongest,
+        );
+        assert_eq!(buffs.apply(&BuffTarget::Spc, 1.0), 5.0);
+    }
+
+    #[test]
+    fn test_old_save_format_loads() {
+        //Buffs saved before ops and durations existed were all multipliers
+        let buff: Buff = serde_json::from_str(
+            r#"{"name":"Frenzy","target":"Sps","multiplier":7.0,"time_left":30.0}"#,
+        )
+        .unwrap();
+        assert_eq!(buff.op, BuffOp::Multiply);
+        assert_eq!(buff.value, 7.0);
+        assert_eq!(buff.fraction_left(), 1.0);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::clickeritem::ClickerItem;
+use crate::costcurve::CostCurve;
+use crate::item::{Item, Reveal};
+use crate::research::{Technology, Unlock};
+use crate::resources::Cost;
+use crate::synergy::Synergy;
+
+//Command unlocked by research that places limit orders on the market
+pub const LIMIT_ORDERS: &str = "limit orders";
+
+//Everything the shop sells, in the order it's listed
+//Saves are synced against this on load so new items show up in old games
+//Most show as ??? until the player has harvested half their cost or owns what they build on
+//Anything without its own cost curve gets 15% more expensive with each one bought
+pub fn items() -> Vec<Item> {
+    let half_cost = Reveal::Spice(0.5);
+    vec![
+        Item::new("Tools", 0, 0.1, 15),
+        Item::new("Fremen", 0, 1.0, 100)
+            .with_curve(CostCurve::Exponential { rate: 0.12 })
+            .revealed_when(half_cost.clone()),
+        Item::new("Ornithopter", 0, 8.0, 1100).revealed_when(half_cost.clone()),
+        //Makes no spice, only water
+        //Water gets scarce, but stills stay cheap enough to build lots of
+        Item::new("Deathstill", 0, 0.0, 3000)
+            .making_water(0.5)
+            .with_curve(CostCurve::Polynomial {
+                power: 2.0,
+                scale: 0.05,
+            })
+            .revealed_when(Reveal::Owns("Fremen", 5)),
+        Item::new("Spice Harvester", 0, 47.0, 12000).revealed_when(half_cost.clone()),
+        Item::with_cost(
+            "Carryall",
+            0,
+            120.0,
+            Cost {
+                spice: 25000,
+                water: 0,
+                solari: 100,
+            },
+        )
+        //The Guild leases carryalls in fleets, every 5 doubles the price
+        .with_curve(CostCurve::Stepwise {
+            tier_size: 5,
+            multiplier: 2.0,
+        })
+        .revealed_when(Reveal::Owns("Spice Harvester", 1)),
+        //Weak on its own but makes up for it during Coriolis storms
+        Item::new("Storm Shelter", 0, 90.0, 40000)
+            .with_curve(CostCurve::Capped {
+                rate: 0.2,
+                max: 50.0,
+            })
+            .revealed_when(half_cost.clone()),
+        //Each one raises melange quality, which CHOAM pays more for
+        Item::with_cost(
+            "Spice Refinery",
+            0,
+            40.0,
+            Cost {
+                spice: 60000,
+                water: 400,
+                solari: 0,
+            },
+        )
+        //Quality tops out after 20, so they get expensive fast
+        .with_curve(CostCurve::Exponential { rate: 0.25 })
+        .revealed_when(Reveal::Owns("Deathstill", 1)),
+        Item::new("Sietch", 0, 260.0, 130000)
+            .making_water(2.0)
+            .revealed_when(half_cost),
+    ]
+}
+
+//Every clicker item the shop sells
+pub fn clicker_items() -> Vec<ClickerItem> {
+    vec![
+        ClickerItem::new("CHOAM Charter", 2.0, 100).revealed_when(Reveal::Spice(0.5)),
+        ClickerItem::new("Guild Satellite", 3.0, 500).revealed_when(Reveal::Spice(0.5)),
+        //These keep clicks worth it once buildings take over, each adds a share of sps
+        ClickerItem::new("Thumper", 1.0, 5000)
+            .adding_sps(0.01)
+            .revealed_when(Reveal::Spice(0.5)),
+        ClickerItem::new("Sandwalk Training", 1.0, 60000)
+            .adding_sps(0.02)
+            .revealed_when(Reveal::Owns("Fremen", 25)),
+        ClickerItem::new("Ornithopter Spotters", 1.0, 250000)
+            .adding_sps(0.03)
+            .revealed_when(Reveal::Owns("Ornithopter", 20)),
+    ]
+}
+
+//Buildings that boost each other's output
+pub fn synergies() -> Vec<Synergy> {
+    vec![
+        Synergy {
+            source: "Sietch",
+            target: "Fremen",
+            bonus_per: 0.01,
+        },
+        Synergy {
+            source: "Ornithopter",
+            target: "Spice Harvester",
+            bonus_per: 0.005,
+        },
+        Synergy {
+            source: "Carryall",
+            target: "Spice Harvester",
+            bonus_per: 0.01,
+        },
+        Synergy {
+            source: "Deathstill",
+            target: "Sietch",
+            bonus_per: 0.02,
+        },
+    ]
+}
+
+//The research tree, prerequisites have to come earlier in the list
+pub fn technologies() -> Vec<Technology> {
+    vec![
+        Technology {
+            name: "Stillsuit Design",
+            prerequisites: &[],
+            cost: 5000,
+            duration: 60.0,
+            unlocks: vec![Unlock::Upgrade(ClickerItem::new(
+                "Improved Stillsuits",
+                2.0,
+                8000,
+            ))],
+        },
+        Technology {
+            name: "Desert Survey",
+            prerequisites: &[],
+            cost: 20000,
+            duration: 120.0,
+            unlocks: vec![Unlock::Item(Item::new("Survey Camp", 0, 150.0, 70000))],
+        },
+        Technology {
+            name: "CHOAM Futures",
+            prerequisites: &["Desert Survey"],
+            cost: 50000,
+            duration: 180.0,
+            unlocks: vec![Unlock::Command(LIMIT_ORDERS)],
+        },
+        Technology {
+            name: "Heavy Carryalls",
+            prerequisites: &["Stillsuit Design", "Desert Survey"],
+            cost: 150000,
+            duration: 300.0,
+            unlocks: vec![Unlock::Item(Item::new(
+                "Harvester Factory",
+                0,
+                1000.0,
+                1000000,
+            ))],
+        },
+        Technology {
+            name: "Worm Riding",
+            prerequisites: &["Heavy Carryalls"],
+            cost: 500000,
+            duration: 600.0,
+            unlocks: vec![Unlock::Upgrade(ClickerItem::new(
+                "Maker Hooks",
+                5.0,
+                600000,
+            ))],
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_research_tree_is_a_dag() {
+        //Every prerequisite exists and comes earlier, so there can't be any cycles
+        let techs = technologies();
+        for (i, tech) in techs.iter().enumerate() {
+            for prerequisite in tech.prerequisites {
+                let position = techs.iter().position(|t| t.name == *prerequisite);
+                assert!(
+                    position.is_some_and(|p| p < i),
+                    "{} needs {}",
+                    tech.name,
+                    prerequisite
+                );
+            }
+        }
+    }
+}
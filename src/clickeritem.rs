@@ -1,9 +1,19 @@
+use crate::item::{Reveal, revealed_in_old_saves};
 use serde::{Deserialize, Serialize};
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ClickerItem {
     name: String,
     multiplier: f64,
     cost: u32,
+    //Fraction of spice per second added to every click, 0.01 is 1%
+    #[serde(default)]
+    sps_share: f64,
+    //Rule for showing it in the shop, comes from the catalog so it isn't saved
+    #[serde(skip)]
+    reveal: Reveal,
+    //Whether the rule has been met, stays shown after that
+    #[serde(default = "revealed_in_old_saves")]
+    revealed: bool,
 }
 
 impl ClickerItem {
@@ -13,16 +23,23 @@ impl ClickerItem {
             name: name.to_string(),
             multiplier,
             cost,
+            sps_share: 0.0,
+            reveal: Reveal::Always,
+            revealed: true,
         }
     }
 
-    //Creates a clone of the clicker item
-    pub fn clone(&self) -> ClickerItem {
-        ClickerItem {
-            name: self.name.clone(),
-            multiplier: self.multiplier,
-            cost: self.cost,
-        }
+    //Makes every click also give a share of spice per second
+    pub fn adding_sps(mut self, sps_share: f64) -> ClickerItem {
+        self.sps_share = sps_share;
+        self
+    }
+
+    //Hides the clicker item in the shop until the rule is met
+    pub fn revealed_when(mut self, reveal: Reveal) -> ClickerItem {
+        self.reveal = reveal;
+        self.revealed = false;
+        self
     }
 
     //Get name of the clicker item
@@ -40,18 +57,63 @@ impl ClickerItem {
         self.cost
     }
 
+    //Get the share of spice per second added to every click
+    pub fn get_sps_share(&self) -> f64 {
+        self.sps_share
+    }
+
+    //Get the rule for showing the clicker item in the shop
+    pub fn get_reveal(&self) -> &Reveal {
+        &self.reveal
+    }
+
+    //Get whether the clicker item is shown in the shop
+    pub fn is_revealed(&self) -> bool {
+        self.revealed
+    }
+
+    //Shows the clicker item in the shop from now on
+    pub fn reveal(&mut self) {
+        self.revealed = true;
+    }
+
+    //Updates the price, what it does and when it's shown to match the catalog
+    pub fn refresh_from(&mut self, catalog_clicker_item: &ClickerItem) {
+        self.cost = catalog_clicker_item.cost;
+        self.multiplier = catalog_clicker_item.multiplier;
+        self.sps_share = catalog_clicker_item.sps_share;
+        self.reveal = catalog_clicker_item.reveal.clone();
+    }
+
     //Get a string with clicker item info for inventory display
     pub fn info_in_inventory(&self) -> String {
-        format!("{}: Click Multiplier: {}", self.name, self.multiplier)
+        format!(
+            "{}: Click Multiplier: {}{}",
+            self.name,
+            self.multiplier,
+            self.sps_info()
+        )
     }
 
     //Get a string with clicker item info for shop display
     pub fn info_in_shop(&self) -> String {
         format!(
-            "{}: Cost: {}, Click Multiplier: {}",
-            self.name, self.cost, self.multiplier
+            "{}: Cost: {}, Click Multiplier: {}{}",
+            self.name,
+            self.cost,
+            self.multiplier,
+            self.sps_info()
         )
     }
+
+    //Extra info for clicker items that add spice per second to clicks, empty otherwise
+    fn sps_info(&self) -> String {
+        if self.sps_share > 0.0 {
+            format!(", Spice per second per click: {}%", self.sps_share * 100.0)
+        } else {
+            String::new()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -75,6 +137,25 @@ mod tests {
         assert_eq!(shop_info, "Test Clicker: Cost: 100, Click Multiplier: 2");
     }
 
+    #[test]
+    fn test_sps_share_info() {
+        let clicker_item = ClickerItem::new("Thumper", 1.0, 5000).adding_sps(0.02);
+        assert_eq!(clicker_item.get_sps_share(), 0.02);
+        assert_eq!(
+            clicker_item.info_in_shop(),
+            "Thumper: Cost: 5000, Click Multiplier: 1, Spice per second per click: 2%"
+        );
+        assert_eq!(clicker_item.clone().get_sps_share(), 0.02);
+    }
+
+    #[test]
+    fn test_old_save_stays_revealed() {
+        let clicker_item: ClickerItem =
+            serde_json::from_str(r#"{"name":"CHOAM Charter","multiplier":2.0,"cost":100}"#)
+                .unwrap();
+        assert!(clicker_item.is_revealed());
+    }
+
     #[test]
     fn test_clicker_item_clone() {
         let og_item = ClickerItem::new("Test Clicker", 2.0, 100);
@@ -0,0 +1,186 @@
+use std::collections::VecDeque;
+
+//How many of the latest gaps between clicks are checked for autoclicker timing
+const REGULARITY_SAMPLES: usize = 40;
+//Gaps varying less than this much relative to their average look machine made, people are nowhere near this steady
+const MIN_VARIATION: f64 = 0.03;
+//Only fast clicking is checked, slow regular clicks aren't worth flagging
+const MAX_CHECKED_GAP: f64 = 0.5;
+
+//How strict the click analyzer is, set from the player's settings
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ClickLimits {
+    //Most clicks credited in any one second, None for no cap
+    pub max_per_second: Option<u32>,
+    //Whether to look for perfectly regular timing
+    pub detect_regular: bool,
+}
+
+impl ClickLimits {
+    //No cap and no checks, what the game uses until it's told otherwise
+    pub const NONE: ClickLimits = ClickLimits {
+        max_per_second: None,
+        detect_regular: false,
+    };
+}
+
+//Watches the gaps between clicks to cap the click rate and spot autoclickers
+//Keeps its own clock, moved along with the game, so it can be fed made up click streams
+pub struct ClickAnalyzer {
+    limits: ClickLimits,
+    clock: f64,
+    //Times of credited clicks in the last second
+    credited: VecDeque<f64>,
+    //Time of the last click, credited or not
+    last_click: Option<f64>,
+    //Latest gaps between clicks, credited or not
+    gaps: VecDeque<f64>,
+    //Whether the timing has looked automated at any point
+    flagged: bool,
+}
+
+impl Default for ClickAnalyzer {
+    fn default() -> ClickAnalyzer {
+        ClickAnalyzer::new(ClickLimits::NONE)
+    }
+}
+
+impl ClickAnalyzer {
+    //Create an analyzer with the given limits
+    pub fn new(limits: ClickLimits) -> ClickAnalyzer {
+        ClickAnalyzer {
+            limits,
+            clock: 0.0,
+            credited: VecDeque::new(),
+            last_click: None,
+            gaps: VecDeque::new(),
+            flagged: false,
+        }
+    }
+
+    //Changes the limits, anything already seen is kept
+    pub fn set_limits(&mut self, limits: ClickLimits) {
+        self.limits = limits;
+    }
+
+    //Gets whether the timing has looked automated
+    pub fn is_flagged(&self) -> bool {
+        self.flagged
+    }
+
+    //Moves the analyzer's clock forward
+    pub fn advance(&mut self, dt: f64) {
+        self.clock += dt;
+    }
+
+    //Registers a click, returns whether it should be credited
+    pub fn click(&mut self) -> bool {
+        if let Some(last) = self.last_click {
+            self.gaps.push_back(self.clock - last);
+            if self.gaps.len() > REGULARITY_SAMPLES {
+                self.gaps.pop_front();
+            }
+        }
+        self.last_click = Some(self.clock);
+        if self.limits.detect_regular && self.looks_automated() {
+            self.flagged = true;
+        }
+
+        while self
+            .credited
+            .front()
+            .is_some_and(|&time| self.clock - time >= 1.0)
+        {
+            self.credited.pop_front();
+        }
+        if let Some(max) = self.limits.max_per_second
+            && self.credited.len() >= max as usize
+        {
+            return false;
+        }
+        self.credited.push_back(self.clock);
+        true
+    }
+
+    //Whether the latest gaps are fast and almost exactly the same length
+    fn looks_automated(&self) -> bool {
+        if self.gaps.len() < REGULARITY_SAMPLES {
+            return false;
+        }
+        let count = self.gaps.len() as f64;
+        let mean = self.gaps.iter().sum::<f64>() / count;
+        if mean <= 0.0 || mean > MAX_CHECKED_GAP {
+            return false;
+        }
+        let variance = self
+            .gaps
+            .iter()
+            .map(|gap| (gap - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        variance.sqrt() / mean < MIN_VARIATION
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    const STRICT: ClickLimits = ClickLimits {
+        max_per_second: Some(10),
+        detect_regular: true,
+    };
+
+    //Feeds the analyzer clicks with the given gaps, returns how many were credited
+    fn feed(analyzer: &mut ClickAnalyzer, gaps: impl Iterator<Item = f64>) -> usize {
+        let mut credited = 0;
+        for gap in gaps {
+            analyzer.advance(gap);
+            if analyzer.click() {
+                credited += 1;
+            }
+        }
+        credited
+    }
+
+    #[test]
+    fn test_rate_cap() {
+        let mut analyzer = ClickAnalyzer::new(STRICT);
+        //50 clicks a second for 2 seconds only gets 10 a second through
+        let credited = feed(&mut analyzer, std::iter::repeat_n(0.02, 100));
+        assert!((19..=21).contains(&credited));
+        //No cap lets everything through
+        let mut analyzer = ClickAnalyzer::default();
+        assert_eq!(feed(&mut analyzer, std::iter::repeat_n(0.0, 100)), 100);
+        assert!(!analyzer.is_flagged());
+    }
+
+    #[test]
+    fn test_regular_clicks_flagged() {
+        let mut analyzer = ClickAnalyzer::new(STRICT);
+        feed(&mut analyzer, std::iter::repeat_n(0.1, 100));
+        assert!(analyzer.is_flagged());
+    }
+
+    #[test]
+    fn test_human_clicks_not_flagged() {
+        //People click about 5 times a second with the gaps all over the place
+        let mut rng = Rng::new(42);
+        let mut analyzer = ClickAnalyzer::new(STRICT);
+        let credited = feed(&mut analyzer, (0..500).map(|_| rng.range(0.12, 0.3)));
+        assert_eq!(credited, 500);
+        assert!(!analyzer.is_flagged());
+        //Slow steady clicks aren't worth flagging either
+        let mut analyzer = ClickAnalyzer::new(STRICT);
+        feed(&mut analyzer, std::iter::repeat_n(1.0, 100));
+        assert!(!analyzer.is_flagged());
+        //Checks can be turned off
+        let mut analyzer = ClickAnalyzer::new(ClickLimits {
+            detect_regular: false,
+            ..STRICT
+        });
+        feed(&mut analyzer, std::iter::repeat_n(0.1, 100));
+        assert!(!analyzer.is_flagged());
+    }
+}
@@ -0,0 +1,48 @@
+//Length of one simulation step in seconds, the game always moves forward this much at a time while open
+pub const STEP: f64 = 0.1;
+//Leftover time this close to a whole step counts as one, so frame times that don't add up exactly still land on it
+const EPSILON: f64 = 1e-9;
+
+//Turns variable frame times into a whole number of fixed steps, carrying the leftover to the next frame
+#[derive(Default)]
+pub struct SimClock {
+    accumulator: f64,
+}
+
+impl SimClock {
+    //Adds a frame's time, returns how many steps to run for it
+    pub fn advance(&mut self, dt: f64) -> u32 {
+        self.accumulator += dt;
+        let mut steps = 0;
+        while self.accumulator >= STEP - EPSILON {
+            self.accumulator -= STEP;
+            steps += 1;
+        }
+        self.accumulator = self.accumulator.max(0.0);
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulates_frames() {
+        let mut clock = SimClock::default();
+        assert_eq!(clock.advance(0.05), 0);
+        assert_eq!(clock.advance(0.05), 1);
+        assert_eq!(clock.advance(0.35), 3);
+        assert_eq!(clock.advance(0.05), 1);
+    }
+
+    #[test]
+    fn test_frame_rate_doesnt_matter() {
+        let mut slow = SimClock::default();
+        let mut fast = SimClock::default();
+        let slow_steps: u32 = (0..3000).map(|_| slow.advance(0.02)).sum();
+        let fast_steps: u32 = (0..12000).map(|_| fast.advance(0.005)).sum();
+        assert_eq!(slow_steps, 600);
+        assert_eq!(fast_steps, 600);
+    }
+}
@@ -0,0 +1,90 @@
+//Clicks closer together than this keep the combo going
+const COMBO_WINDOW: f64 = 0.5;
+//Seconds of unbroken clicking it takes to reach the biggest multiplier
+const FULL_STREAK: f64 = 20.0;
+//Biggest multiplier a combo can reach
+pub const MAX_COMBO: f64 = 2.0;
+//How many seconds of streak are lost per second without clicking
+const DECAY: f64 = 2.0;
+
+//Multiplier on clicks that grows the longer the player keeps clicking fast
+//Grows with time rather than click count, so a burst of clicks in one frame doesn't max it out
+pub struct Combo {
+    //Seconds since the last click
+    since_click: f64,
+    //Seconds of fast clicking built up so far
+    streak: f64,
+}
+
+impl Default for Combo {
+    fn default() -> Combo {
+        Combo {
+            since_click: f64::INFINITY,
+            streak: 0.0,
+        }
+    }
+}
+
+impl Combo {
+    //Gets the current multiplier, 1 with no combo going
+    pub fn get_multiplier(&self) -> f64 {
+        1.0 + (MAX_COMBO - 1.0) * self.streak / FULL_STREAK
+    }
+
+    //Registers a click, returns the multiplier it gets
+    pub fn click(&mut self) -> f64 {
+        self.since_click = 0.0;
+        self.get_multiplier()
+    }
+
+    //Builds the streak while clicks keep coming, lets it fall off once they stop
+    pub fn advance(&mut self, dt: f64) {
+        self.since_click += dt;
+        if self.since_click <= COMBO_WINDOW {
+            self.streak = (self.streak + dt).min(FULL_STREAK);
+        } else {
+            self.streak = (self.streak - dt * DECAY).max(0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combo_grows_and_decays() {
+        let mut combo = Combo::default();
+        //Lots of clicks at once don't build anything
+        for _ in 0..100 {
+            assert_eq!(combo.click(), 1.0);
+        }
+        //Four clicks a second for 10 seconds gets halfway
+        for _ in 0..40 {
+            combo.advance(0.25);
+            combo.click();
+        }
+        assert!((combo.get_multiplier() - 1.5).abs() < 1e-9);
+        //Keeping it up caps out
+        for _ in 0..400 {
+            combo.advance(0.25);
+            combo.click();
+        }
+        assert_eq!(combo.get_multiplier(), MAX_COMBO);
+        //Stopping loses it twice as fast as it built up
+        combo.advance(5.5);
+        assert!(combo.get_multiplier() < MAX_COMBO);
+        combo.advance(10.0);
+        assert_eq!(combo.get_multiplier(), 1.0);
+    }
+
+    #[test]
+    fn test_slow_clicks_dont_build() {
+        let mut combo = Combo::default();
+        for _ in 0..20 {
+            combo.advance(1.0);
+            combo.click();
+        }
+        assert_eq!(combo.get_multiplier(), 1.0);
+    }
+}
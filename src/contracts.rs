@@ -0,0 +1,387 @@
+use crate::buffs::{Buff, BuffTarget};
+use crate::clickeritem::ClickerItem;
+use crate::market::MEAN_PRICE;
+use crate::rng::Rng;
+use serde::{Deserialize, Serialize};
+
+//Most offers on the board at once
+const MAX_OFFERS: usize = 3;
+//Most contracts the player can have accepted at once
+pub const MAX_ACTIVE: usize = 2;
+//Seconds between new offers
+const OFFER_GAP: f64 = 180.0;
+//Upgrade only a click contract can give, once per run
+const RUSH_UPGRADE: &str = "Guild Rush Gloves";
+
+//Who offers the contract
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Issuer {
+    Choam,
+    Guild,
+}
+
+impl Issuer {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Issuer::Choam => "CHOAM",
+            Issuer::Guild => "Spacing Guild",
+        }
+    }
+}
+
+//What the player has to do
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Objective {
+    //Have this much spice on hand, it's handed over when the contract completes
+    Deliver { spice: f64 },
+    //Own at least this many of a building
+    Own { building: String, count: u32 },
+    //Harvest by hand this many times after accepting
+    Clicks { count: u64 },
+}
+
+//What completing the contract gives
+#[derive(Serialize, Deserialize)]
+pub enum Reward {
+    Spice(f64),
+    Solari(f64),
+    Buff(Buff),
+    //Clicker item the shop doesn't sell
+    Upgrade(ClickerItem),
+}
+
+//A contract on the board or accepted by the player
+#[derive(Serialize, Deserialize)]
+pub struct Contract {
+    pub issuer: Issuer,
+    pub objective: Objective,
+    pub reward: Reward,
+    //Spice lost if the time runs out
+    pub penalty: f64,
+    //Seconds left once accepted, the whole time limit while still an offer
+    pub time_left: f64,
+    //Total clicks when accepted, so click objectives only count new ones
+    pub start_clicks: u64,
+}
+
+impl Contract {
+    //Whether the objective is met, given the spice on hand, total clicks and a way to count buildings
+    pub fn is_met(&self, spice: f64, clicks: u64, owned: impl Fn(&str) -> u32) -> bool {
+        match &self.objective {
+            Objective::Deliver { spice: needed } => spice >= *needed,
+            Objective::Own { building, count } => owned(building) >= *count,
+            Objective::Clicks { count } => clicks.saturating_sub(self.start_clicks) >= *count,
+        }
+    }
+
+    //How far along the objective is, like "12/150"
+    pub fn progress(&self, spice: f64, clicks: u64, owned: impl Fn(&str) -> u32) -> String {
+        match &self.objective {
+            Objective::Deliver { spice: needed } => {
+                format!("{:.0}/{:.0}", spice.min(*needed), needed)
+            }
+            Objective::Own { building, count } => {
+                format!("{}/{}", owned(building).min(*count), count)
+            }
+            Objective::Clicks { count } => format!(
+                "{}/{}",
+                clicks.saturating_sub(self.start_clicks).min(*count),
+                count
+            ),
+        }
+    }
+
+    //One line summary for the contracts panel
+    pub fn describe(&self) -> String {
+        let objective = match &self.objective {
+            Objective::Deliver { spice } => format!("Deliver {:.0} spice", spice),
+            Objective::Own { building, count } => format!("Own {} {}", count, building),
+            Objective::Clicks { count } => format!("Harvest by hand {} times", count),
+        };
+        let reward = match &self.reward {
+            Reward::Spice(spice) => format!("{:.0} spice", spice),
+            Reward::Solari(solari) => format!("{:.2} solari", solari),
+            Reward::Buff(buff) => format!("{} ({})", buff.name, buff.describe()),
+            Reward::Upgrade(clicker_item) => format!(
+                "{} (x{} spice per click)",
+                clicker_item.get_name(),
+                clicker_item.get_multiplier()
+            ),
+        };
+        format!(
+            "{}: {} within {:.0}s for {}, fail and lose {:.0} spice",
+            self.issuer.name(),
+            objective,
+            self.time_left.ceil(),
+            reward,
+            self.penalty
+        )
+    }
+}
+
+//What the game looks like right now, used to size new contracts
+pub struct ContractContext<'a> {
+    pub sps: f64,
+    //Every building and how many are owned
+    pub buildings: Vec<(&'a str, u32)>,
+    //Whether the click contract's upgrade is already owned
+    pub has_rush_upgrade: bool,
+}
+
+//Templates contracts are generated from
+#[derive(Clone, Copy)]
+enum Template {
+    DeliverSpice,
+    OwnBuildings,
+    ClickRush,
+}
+
+impl Template {
+    const ALL: [Template; 3] = [
+        Template::DeliverSpice,
+        Template::OwnBuildings,
+        Template::ClickRush,
+    ];
+
+    //Fills in a template, amounts scale with the player's sps so contracts stay worth doing
+    fn generate(&self, rng: &mut Rng, context: &ContractContext) -> Contract {
+        //A minute of production, with a floor so early contracts aren't trivial
+        let minute = (context.sps * 60.0).max(100.0);
+        match self {
+            Template::DeliverSpice => {
+                let spice = (minute * rng.range(3.0, 8.0)).round();
+                Contract {
+                    issuer: Issuer::Choam,
+                    objective: Objective::Deliver { spice },
+                    //CHOAM pays double the usual price
+                    reward: Reward::Solari(spice * MEAN_PRICE * 2.0),
+                    penalty: (spice * 0.1).round(),
+                    time_left: 600.0,
+                    start_clicks: 0,
+                }
+            }
+            Template::OwnBuildings => {
+                let index = (rng.next_f64() * context.buildings.len() as f64) as usize;
+                let (building, owned) = context
+                    .buildings
+                    .get(index)
+                    .copied()
+                    .unwrap_or(("Tools", 0));
+                let extra = rng.range(3.0, 9.0) as u32;
+                Contract {
+                    issuer: Issuer::Guild,
+                    objective: Objective::Own {
+                        building: building.to_string(),
+                        count: owned + extra,
+                    },
+                    reward: Reward::Buff(Buff::multiply(
+                        "Guild Favor",
+                        BuffTarget::Sps,
+                        2.0,
+                        120.0,
+                    )),
+                    penalty: minute.round(),
+                    time_left: 900.0,
+                    start_clicks: 0,
+                }
+            }
+            Template::ClickRush => {
+                let count = rng.range(100.0, 250.0) as u64;
+                let reward = if context.has_rush_upgrade {
+                    Reward::Spice((minute * 2.0).round())
+                } else {
+                    Reward::Upgrade(ClickerItem::new(RUSH_UPGRADE, 1.5, 0))
+                };
+                Contract {
+                    issuer: Issuer::Guild,
+                    objective: Objective::Clicks { count },
+                    reward,
+                    penalty: (minute / 2.0).round(),
+                    time_left: 120.0,
+                    start_clicks: 0,
+                }
+            }
+        }
+    }
+}
+
+//Gets whether an upgrade name is the one click contracts give
+pub fn is_rush_upgrade(name: &str) -> bool {
+    name == RUSH_UPGRADE
+}
+
+//Contract board and the contracts the player took on, saved with their remaining time
+#[derive(Serialize, Deserialize)]
+pub struct Contracts {
+    rng: Rng,
+    //Seconds until a new offer shows up
+    next_offer_in: f64,
+    offers: Vec<Contract>,
+    active: Vec<Contract>,
+}
+
+impl Default for Contracts {
+    fn default() -> Contracts {
+        Contracts::new(Rng::from_time().next_u64())
+    }
+}
+
+impl Contracts {
+    //Create an empty board from a seed, the first offer comes right away
+    pub fn new(seed: u64) -> Contracts {
+        Contracts {
+            rng: Rng::new(seed),
+            next_offer_in: 0.0,
+            offers: vec![],
+            active: vec![],
+        }
+    }
+
+    //Gets the contracts on offer
+    pub fn get_offers(&self) -> &[Contract] {
+        &self.offers
+    }
+
+    //Gets the accepted contracts
+    pub fn get_active(&self) -> &[Contract] {
+        &self.active
+    }
+
+    //Gets the seconds until a new offer shows up or an accepted contract runs out, whichever is first
+    pub fn next_change(&self) -> f64 {
+        self.active
+            .iter()
+            .map(|contract| contract.time_left)
+            .fold(self.next_offer_in, f64::min)
+    }
+
+    //Posts new offers as their timer runs out, oldest offer makes room when the board is full
+    //Returns how many new offers were posted
+    pub fn post_offers(&mut self, dt: f64, context: &ContractContext) -> usize {
+        let mut posted = 0;
+        self.next_offer_in -= dt;
+        while self.next_offer_in <= 0.0 {
+            self.next_offer_in += OFFER_GAP;
+            let template = Template::ALL[(self.rng.next_f64() * 3.0) as usize];
+            let mut contract = template.generate(&mut self.rng, context);
+            //Only one chance at the unique upgrade on the board at a time
+            if matches!(contract.reward, Reward::Upgrade(_)) && self.rush_upgrade_posted() {
+                contract = Template::DeliverSpice.generate(&mut self.rng, context);
+            }
+            if self.offers.len() >= MAX_OFFERS {
+                self.offers.remove(0);
+            }
+            self.offers.push(contract);
+            posted += 1;
+        }
+        posted
+    }
+
+    //Whether an offer or active contract already gives the unique upgrade
+    fn rush_upgrade_posted(&self) -> bool {
+        self.offers
+            .iter()
+            .chain(self.active.iter())
+            .any(|c| matches!(c.reward, Reward::Upgrade(_)))
+    }
+
+    //Accepts an offer by its 0-based position, returns false if there's no such offer or no room
+    pub fn accept(&mut self, index: usize, clicks: u64) -> bool {
+        if index >= self.offers.len() || self.active.len() >= MAX_ACTIVE {
+            return false;
+        }
+        let mut contract = self.offers.remove(index);
+        contract.start_clicks = clicks;
+        self.active.push(contract);
+        true
+    }
+
+    //Removes and returns the first accepted contract whose objective is met
+    //One at a time so two deliveries can't both count the same spice
+    pub fn take_completed(&mut self, met: impl Fn(&Contract) -> bool) -> Option<Contract> {
+        let index = self.active.iter().position(met)?;
+        Some(self.active.remove(index))
+    }
+
+    //Counts down accepted contracts, removes and returns the ones that ran out of time
+    pub fn advance(&mut self, dt: f64) -> Vec<Contract> {
+        for contract in self.active.iter_mut() {
+            contract.time_left -= dt;
+        }
+        let (failed, waiting) = std::mem::take(&mut self.active)
+            .into_iter()
+            .partition(|contract| contract.time_left <= 0.0);
+        self.active = waiting;
+        failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> ContractContext<'static> {
+        ContractContext {
+            sps: 10.0,
+            buildings: vec![("Fremen", 4)],
+            has_rush_upgrade: false,
+        }
+    }
+
+    #[test]
+    fn test_offers_fill_board() {
+        let mut contracts = Contracts::new(5);
+        assert_eq!(contracts.post_offers(0.0, &context()), 1);
+        //A long time away posts plenty, but the board only keeps the newest few
+        contracts.post_offers(OFFER_GAP * 10.0, &context());
+        assert_eq!(contracts.get_offers().len(), MAX_OFFERS);
+        let upgrades = contracts
+            .get_offers()
+            .iter()
+            .filter(|c| matches!(c.reward, Reward::Upgrade(_)))
+            .count();
+        assert!(upgrades <= 1);
+    }
+
+    #[test]
+    fn test_accept_complete_and_fail() {
+        let mut contracts = Contracts::new(9);
+        contracts.post_offers(OFFER_GAP * 2.0, &context());
+        assert!(!contracts.accept(MAX_OFFERS, 0));
+        assert!(contracts.accept(0, 7));
+        assert!(contracts.accept(0, 7));
+        //Only two at once
+        assert!(!contracts.accept(0, 7));
+        assert_eq!(contracts.get_active()[0].start_clicks, 7);
+        assert!(contracts.take_completed(|_| false).is_none());
+        assert!(contracts.take_completed(|_| true).is_some());
+        assert!(contracts.take_completed(|_| true).is_some());
+        assert!(contracts.get_active().is_empty());
+
+        contracts.post_offers(OFFER_GAP, &context());
+        assert!(contracts.accept(0, 0));
+        assert!(contracts.advance(1.0).is_empty());
+        //Nothing lasts longer than 15 minutes
+        assert_eq!(contracts.advance(900.0).len(), 1);
+        assert!(contracts.get_active().is_empty());
+    }
+
+    #[test]
+    fn test_generated_from_context() {
+        let mut rng = Rng::new(1);
+        let contract = Template::OwnBuildings.generate(&mut rng, &context());
+        match contract.objective {
+            Objective::Own { building, count } => {
+                assert_eq!(building, "Fremen");
+                assert!((7..13).contains(&count));
+            }
+            _ => panic!("Own template made a different objective"),
+        }
+        let owned = ContractContext {
+            has_rush_upgrade: true,
+            ..context()
+        };
+        let contract = Template::ClickRush.generate(&mut rng, &owned);
+        assert!(matches!(contract.reward, Reward::Spice(_)));
+    }
+}
@@ -0,0 +1,73 @@
+//How an item's price grows with the number owned, each gives the factor the base cost is multiplied by
+#[derive(Clone, PartialEq, Debug)]
+pub enum CostCurve {
+    //Goes up by the same fraction each time, 0.15 is 15% per one owned
+    Exponential { rate: f64 },
+    //Base cost times 1 + scale * owned^power, grows quickly at first then much slower than exponential
+    Polynomial { power: f64, scale: f64 },
+    //Flat within a tier, multiplied every tier_size owned
+    Stepwise { tier_size: u32, multiplier: f64 },
+    //Exponential until it reaches max times the base cost, then stays there
+    Capped { rate: f64, max: f64 },
+}
+
+//Every item used to get 15% more expensive per purchase, which is still the usual curve
+impl Default for CostCurve {
+    fn default() -> CostCurve {
+        CostCurve::Exponential { rate: 0.15 }
+    }
+}
+
+impl CostCurve {
+    //Gets what the base cost is multiplied by when owned are already owned
+    pub fn factor(&self, owned: u32) -> f64 {
+        let n = owned as f64;
+        match self {
+            CostCurve::Exponential { rate } => (1.0 + rate).powf(n),
+            CostCurve::Polynomial { power, scale } => 1.0 + scale * n.powf(*power),
+            CostCurve::Stepwise {
+                tier_size,
+                multiplier,
+            } => multiplier.powi((owned / (*tier_size).max(1)) as i32),
+            CostCurve::Capped { rate, max } => (1.0 + rate).powf(n).min(*max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_curves() {
+        let exponential = CostCurve::default();
+        assert_eq!(exponential.factor(0), 1.0);
+        assert!(close(exponential.factor(2), 1.3225));
+
+        let polynomial = CostCurve::Polynomial {
+            power: 2.0,
+            scale: 0.5,
+        };
+        assert!(close(polynomial.factor(4), 9.0));
+
+        let stepwise = CostCurve::Stepwise {
+            tier_size: 5,
+            multiplier: 2.0,
+        };
+        assert_eq!(stepwise.factor(4), 1.0);
+        assert_eq!(stepwise.factor(5), 2.0);
+        assert_eq!(stepwise.factor(12), 4.0);
+
+        let capped = CostCurve::Capped {
+            rate: 1.0,
+            max: 10.0,
+        };
+        assert_eq!(capped.factor(3), 8.0);
+        assert_eq!(capped.factor(4), 10.0);
+        assert_eq!(capped.factor(100), 10.0);
+    }
+}
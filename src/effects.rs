@@ -0,0 +1,159 @@
+use crate::rng::Rng;
+
+//Most particles alive at once, new bursts are trimmed once this is reached
+pub const MAX_PARTICLES: usize = 300;
+//Most floating labels alive at once, the oldest is dropped to make room
+pub const MAX_LABELS: usize = 20;
+//Particles spawned per click
+const BURST_SIZE: usize = 12;
+//Seconds a particle lives
+const PARTICLE_LIFE: f32 = 0.8;
+//Seconds a floating label lives
+const LABEL_LIFE: f32 = 1.2;
+//Downward pull on particles, in pixels per second squared
+const GRAVITY: f32 = 600.0;
+//How fast labels float up, in pixels per second
+const LABEL_RISE: f32 = 60.0;
+
+//A grain of sand or spice thrown out by a click
+pub struct Particle {
+    pub x: f32,
+    pub y: f32,
+    vx: f32,
+    vy: f32,
+    //Seconds left before it disappears
+    life: f32,
+    //True for orange spice, false for sand
+    pub spice: bool,
+}
+
+impl Particle {
+    //Gets how faded the particle is, 1 is fully visible
+    pub fn alpha(&self) -> f32 {
+        (self.life / PARTICLE_LIFE).clamp(0.0, 1.0)
+    }
+}
+
+//"+N" text that floats up from where the player clicked
+pub struct FloatingLabel {
+    pub x: f32,
+    pub y: f32,
+    pub text: String,
+    life: f32,
+}
+
+impl FloatingLabel {
+    //Gets how faded the label is, 1 is fully visible
+    pub fn alpha(&self) -> f32 {
+        (self.life / LABEL_LIFE).clamp(0.0, 1.0)
+    }
+}
+
+//Click feedback visuals, only looks, nothing here changes the game
+pub struct Effects {
+    particles: Vec<Particle>,
+    labels: Vec<FloatingLabel>,
+    rng: Rng,
+}
+
+impl Effects {
+    //Create an empty set of effects
+    pub fn new(rng: Rng) -> Effects {
+        Effects {
+            particles: vec![],
+            labels: vec![],
+            rng,
+        }
+    }
+
+    //Gets the live particles
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    //Gets the live floating labels
+    pub fn labels(&self) -> &[FloatingLabel] {
+        &self.labels
+    }
+
+    //Adds a "+amount" label and a burst of particles at the click position
+    pub fn spawn_click(&mut self, x: f32, y: f32, amount: f64) {
+        if self.labels.len() >= MAX_LABELS {
+            self.labels.remove(0);
+        }
+        self.labels.push(FloatingLabel {
+            x,
+            y,
+            text: format!("+{:.2}", amount),
+            life: LABEL_LIFE,
+        });
+
+        let room = MAX_PARTICLES - self.particles.len();
+        for _ in 0..BURST_SIZE.min(room) {
+            let angle = self.rng.range(0.0, std::f64::consts::TAU) as f32;
+            let speed = self.rng.range(80.0, 260.0) as f32;
+            self.particles.push(Particle {
+                x,
+                y,
+                vx: angle.cos() * speed,
+                //Thrown a little upwards so the burst arcs before falling
+                vy: angle.sin() * speed - 150.0,
+                life: self.rng.range(0.5, 1.0) as f32 * PARTICLE_LIFE,
+                spice: self.rng.chance(0.5),
+            });
+        }
+    }
+
+    //Moves everything forward and removes anything that's faded out
+    pub fn update(&mut self, dt: f32) {
+        for particle in self.particles.iter_mut() {
+            particle.vy += GRAVITY * dt;
+            particle.x += particle.vx * dt;
+            particle.y += particle.vy * dt;
+            particle.life -= dt;
+        }
+        self.particles.retain(|particle| particle.life > 0.0);
+
+        for label in self.labels.iter_mut() {
+            label.y -= LABEL_RISE * dt;
+            label.life -= dt;
+        }
+        self.labels.retain(|label| label.life > 0.0);
+    }
+
+    //Removes all effects, used when effects get turned off
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.labels.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_and_expire() {
+        let mut effects = Effects::new(Rng::new(1));
+        effects.spawn_click(100.0, 100.0, 2.0);
+        assert_eq!(effects.particles().len(), BURST_SIZE);
+        assert_eq!(effects.labels()[0].text, "+2.00");
+        //Label floats upwards
+        effects.update(0.5);
+        assert!(effects.labels()[0].y < 100.0);
+        //Everything is gone once its life runs out
+        effects.update(LABEL_LIFE);
+        assert!(effects.particles().is_empty());
+        assert!(effects.labels().is_empty());
+    }
+
+    #[test]
+    fn test_caps() {
+        let mut effects = Effects::new(Rng::new(1));
+        for _ in 0..100 {
+            effects.spawn_click(0.0, 0.0, 1.0);
+        }
+        assert_eq!(effects.particles().len(), MAX_PARTICLES);
+        assert_eq!(effects.labels().len(), MAX_LABELS);
+    }
+}
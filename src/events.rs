@@ -0,0 +1,197 @@
+use crate::rng::Rng;
+use serde::{Deserialize, Serialize};
+
+//Shortest and longest wait between random events, in seconds
+const MIN_EVENT_GAP: f64 = 240.0;
+const MAX_EVENT_GAP: f64 = 600.0;
+//Buildings a sandworm can swallow
+pub const SANDWORM_TARGETS: [&str; 2] = ["Ornithopter", "Spice Harvester"];
+//Fraction of each target building lost if the player doesn't evacuate
+pub const SANDWORM_LOSS: f64 = 0.25;
+//Seconds of sps given as a reward for evacuating in time
+pub const EVACUATION_BONUS_SECONDS: f64 = 60.0;
+
+//Kinds of random event, only sandworms for now
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum EventKind {
+    Sandworm,
+}
+
+impl EventKind {
+    //Seconds the player has to respond
+    pub fn warning_time(&self) -> f64 {
+        match self {
+            EventKind::Sandworm => 15.0,
+        }
+    }
+
+    //Text shown in the warning banner
+    pub fn warning(&self) -> &'static str {
+        match self {
+            EventKind::Sandworm => "Wormsign! Evacuate your harvesters",
+        }
+    }
+}
+
+//An event waiting on the player
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ActiveEvent {
+    pub kind: EventKind,
+    //Seconds left to respond
+    pub time_left: f64,
+}
+
+//Something that changed while advancing events
+#[derive(PartialEq, Debug)]
+pub enum EventUpdate {
+    Started(EventKind),
+    //The player didn't respond in time
+    Expired(EventKind),
+}
+
+//Schedules random events, seeded so a run can be repeated in tests
+#[derive(Serialize, Deserialize)]
+pub struct Events {
+    rng: Rng,
+    //Seconds until the next event, only counts down while no event is active
+    next_event_in: f64,
+    active: Option<ActiveEvent>,
+}
+
+impl Default for Events {
+    fn default() -> Events {
+        Events::new(Rng::from_time().next_u64())
+    }
+}
+
+impl Events {
+    //Create an event schedule from a seed
+    pub fn new(seed: u64) -> Events {
+        let mut rng = Rng::new(seed);
+        let next_event_in = rng.range(MIN_EVENT_GAP, MAX_EVENT_GAP);
+        Events {
+            rng,
+            next_event_in,
+            active: None,
+        }
+    }
+
+    //Gets the event waiting on the player, if any
+    pub fn get_active(&self) -> Option<&ActiveEvent> {
+        self.active.as_ref()
+    }
+
+    //Counts down timers, can_start is false when there's nothing an event could threaten
+    pub fn advance(&mut self, dt: f64, can_start: bool) -> Option<EventUpdate> {
+        if let Some(active) = self.active.as_mut() {
+            active.time_left -= dt;
+            if active.time_left > 0.0 {
+                return None;
+            }
+            let kind = active.kind;
+            self.active = None;
+            self.schedule_next();
+            return Some(EventUpdate::Expired(kind));
+        }
+
+        self.next_event_in -= dt;
+        if self.next_event_in > 0.0 {
+            return None;
+        }
+        self.schedule_next();
+        //Nothing to threaten, so skip this one and wait for the next
+        if !can_start {
+            return None;
+        }
+        self.start(EventKind::Sandworm);
+        Some(EventUpdate::Started(EventKind::Sandworm))
+    }
+
+    //Starts an event right away
+    pub fn start(&mut self, kind: EventKind) {
+        self.active = Some(ActiveEvent {
+            kind,
+            time_left: kind.warning_time(),
+        });
+    }
+
+    //Player responded to the event, returns which one it was
+    pub fn resolve(&mut self) -> Option<EventKind> {
+        let kind = self.active.take()?.kind;
+        self.schedule_next();
+        Some(kind)
+    }
+
+    fn schedule_next(&mut self) {
+        self.next_event_in = self.rng.range(MIN_EVENT_GAP, MAX_EVENT_GAP);
+    }
+}
+
+//Gets how many of a building a sandworm takes, at least one if any are owned
+pub fn sandworm_losses(owned: u32) -> u32 {
+    if owned == 0 {
+        return 0;
+    }
+    ((owned as f64 * SANDWORM_LOSS).floor() as u32).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Steps one second at a time until something happens
+    fn run_until_update(
+        events: &mut Events,
+        can_start: bool,
+        max_seconds: u32,
+    ) -> Option<EventUpdate> {
+        for _ in 0..max_seconds {
+            if let Some(update) = events.advance(1.0, can_start) {
+                return Some(update);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_event_starts_and_expires() {
+        let mut events = Events::new(5);
+        let started = run_until_update(&mut events, true, MAX_EVENT_GAP as u32 + 1);
+        assert_eq!(started, Some(EventUpdate::Started(EventKind::Sandworm)));
+        assert!(events.get_active().is_some());
+        let expired = run_until_update(&mut events, true, 100);
+        assert_eq!(expired, Some(EventUpdate::Expired(EventKind::Sandworm)));
+        assert!(events.get_active().is_none());
+    }
+
+    #[test]
+    fn test_same_seed_same_schedule() {
+        let mut a = Events::new(99);
+        let mut b = Events::new(99);
+        for _ in 0..2000 {
+            assert_eq!(a.advance(1.0, true), b.advance(1.0, true));
+        }
+    }
+
+    #[test]
+    fn test_no_event_without_targets() {
+        let mut events = Events::new(5);
+        assert_eq!(run_until_update(&mut events, false, 5000), None);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut events = Events::new(1);
+        assert_eq!(events.resolve(), None);
+        events.start(EventKind::Sandworm);
+        assert_eq!(events.resolve(), Some(EventKind::Sandworm));
+        assert!(events.get_active().is_none());
+    }
+
+    #[test]
+    fn test_sandworm_losses() {
+        assert_eq!(sandworm_losses(0), 0);
+        assert_eq!(sandworm_losses(1), 1);
+        assert_eq!(sandworm_losses(8), 2);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::buffs::BuffTarget;
+use crate::clickeritem::ClickerItem;
+use crate::item::Item;
+use crate::resources::Cost;
+use serde::{Deserialize, Serialize};
+
+//Spice per second needed before the factions take notice
+pub const PLEDGE_SPS: f64 = 500.0;
+
+//Powers of the Imperium the player can swear allegiance to, once per prestige
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Faction {
+    Atreides,
+    Harkonnen,
+    Fremen,
+    BeneGesserit,
+    SpacingGuild,
+}
+
+impl Faction {
+    pub const ALL: [Faction; 5] = [
+        Faction::Atreides,
+        Faction::Harkonnen,
+        Faction::Fremen,
+        Faction::BeneGesserit,
+        Faction::SpacingGuild,
+    ];
+
+    //Full name shown to the player
+    pub fn name(&self) -> &'static str {
+        match self {
+            Faction::Atreides => "House Atreides",
+            Faction::Harkonnen => "House Harkonnen",
+            Faction::Fremen => "the Fremen",
+            Faction::BeneGesserit => "the Bene Gesserit",
+            Faction::SpacingGuild => "the Spacing Guild",
+        }
+    }
+
+    //Finds a faction by name, ignoring case and the "house"/"the" in front
+    pub fn parse(name: &str) -> Option<Faction> {
+        let name = name.trim().to_ascii_lowercase();
+        Faction::ALL.into_iter().find(|faction| {
+            let full = faction.name().to_ascii_lowercase();
+            let short = full
+                .trim_start_matches("house ")
+                .trim_start_matches("the ")
+                .to_string();
+            name == full || name == short || (name == "guild" && *faction == Faction::SpacingGuild)
+        })
+    }
+
+    //Multipliers the faction applies for as long as the player is pledged
+    fn modifiers(&self) -> Vec<(BuffTarget, f64)> {
+        let building = |name: &str| BuffTarget::Building(name.to_string());
+        match self {
+            Faction::Atreides => vec![(BuffTarget::Sps, 1.1), (BuffTarget::Spc, 1.5)],
+            Faction::Harkonnen => vec![
+                (building("Spice Harvester"), 1.5),
+                (building("Fremen"), 0.8),
+            ],
+            Faction::Fremen => vec![(building("Fremen"), 2.0), (building("Sietch"), 1.25)],
+            Faction::BeneGesserit => vec![(BuffTarget::Spc, 3.0)],
+            Faction::SpacingGuild => vec![(BuffTarget::Sps, 1.25)],
+        }
+    }
+
+    //Gets the combined multiplier the faction applies to a target, 1 if it doesn't touch it
+    pub fn multiplier(&self, target: &BuffTarget) -> f64 {
+        self.modifiers()
+            .iter()
+            .filter(|(t, _)| t == target)
+            .map(|(_, multiplier)| multiplier)
+            .product()
+    }
+
+    //One line summary of what pledging does
+    pub fn describe(&self) -> String {
+        let effects: Vec<String> = self
+            .modifiers()
+            .iter()
+            .map(|(target, multiplier)| {
+                let target = match target {
+                    BuffTarget::Sps => String::from("spice per second"),
+                    BuffTarget::Spc => String::from("spice per click"),
+                    BuffTarget::Building(name) => name.clone(),
+                };
+                format!("x{} {}", multiplier, target)
+            })
+            .collect();
+        format!(
+            "{}: {}, unlocks {} and {}",
+            self.name(),
+            effects.join(", "),
+            self.items()[0].get_name(),
+            self.clicker_items()[0].get_name()
+        )
+    }
+
+    //Buildings only this faction's followers can build
+    pub fn items(&self) -> Vec<Item> {
+        match self {
+            Faction::Atreides => vec![Item::new("Arrakeen Palace", 0, 500.0, 250000)],
+            Faction::Harkonnen => vec![Item::new("Slave Pits", 0, 420.0, 180000)],
+            Faction::Fremen => vec![Item::new("Wind Trap", 0, 60.0, 60000).making_water(5.0)],
+            Faction::BeneGesserit => vec![Item::new("Missionaria Outpost", 0, 320.0, 150000)],
+            Faction::SpacingGuild => vec![Item::with_cost(
+                "Guild Bank",
+                0,
+                650.0,
+                Cost {
+                    spice: 200000,
+                    water: 0,
+                    solari: 2000,
+                },
+            )],
+        }
+    }
+
+    //Clicker items only this faction's followers can buy
+    pub fn clicker_items(&self) -> Vec<ClickerItem> {
+        match self {
+            Faction::Atreides => vec![ClickerItem::new("Ducal Signet", 4.0, 50000)],
+            Faction::Harkonnen => vec![ClickerItem::new("Baron's Lash", 5.0, 80000)],
+            Faction::Fremen => vec![ClickerItem::new("Crysknife", 4.0, 40000)],
+            Faction::BeneGesserit => vec![ClickerItem::new("The Voice", 6.0, 120000)],
+            Faction::SpacingGuild => vec![ClickerItem::new("Navigator's Sight", 5.0, 90000)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Faction::parse("Atreides"), Some(Faction::Atreides));
+        assert_eq!(Faction::parse("house harkonnen"), Some(Faction::Harkonnen));
+        assert_eq!(Faction::parse("bene gesserit"), Some(Faction::BeneGesserit));
+        assert_eq!(Faction::parse("guild"), Some(Faction::SpacingGuild));
+        assert_eq!(Faction::parse("corrino"), None);
+    }
+
+    #[test]
+    fn test_multiplier() {
+        let fremen = BuffTarget::Building(String::from("Fremen"));
+        assert_eq!(Faction::Fremen.multiplier(&fremen), 2.0);
+        assert_eq!(Faction::Harkonnen.multiplier(&fremen), 0.8);
+        assert_eq!(Faction::Atreides.multiplier(&fremen), 1.0);
+        assert_eq!(Faction::BeneGesserit.multiplier(&BuffTarget::Spc), 3.0);
+    }
+}
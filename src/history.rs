@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+//How far back the graph can look
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GraphRange {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl GraphRange {
+    //Ranges in the order their buttons are drawn
+    pub const ALL: [GraphRange; 3] = [GraphRange::Minute, GraphRange::Hour, GraphRange::Day];
+
+    //Text drawn on the range button
+    pub fn label(&self) -> &'static str {
+        match self {
+            GraphRange::Minute => "Minute",
+            GraphRange::Hour => "Hour",
+            GraphRange::Day => "Day",
+        }
+    }
+
+    //Seconds between samples
+    pub fn interval(&self) -> f64 {
+        match self {
+            GraphRange::Minute => 1.0,
+            GraphRange::Hour => 60.0,
+            GraphRange::Day => 600.0,
+        }
+    }
+
+    //Number of samples kept, interval * capacity is the whole range
+    pub fn capacity(&self) -> usize {
+        match self {
+            GraphRange::Minute => 60,
+            GraphRange::Hour => 60,
+            GraphRange::Day => 144,
+        }
+    }
+
+    //Total seconds the range covers
+    pub fn span(&self) -> f64 {
+        self.interval() * self.capacity() as f64
+    }
+}
+
+//One point on the graph
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sample {
+    //Seconds before now the sample was taken
+    pub age: f64,
+    pub spice: f64,
+    pub sps: f64,
+}
+
+//Something worth pointing out on the graph, like a purchase
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Marker {
+    //Game time the marker was added at
+    pub time: f64,
+    pub label: String,
+}
+
+//Samples at one resolution, sample times aren't saved since they're evenly spaced back from last_time
+#[derive(Serialize, Deserialize, Default)]
+struct Series {
+    //Game time of the newest sample
+    last_time: f64,
+    spice: VecDeque<f64>,
+    sps: VecDeque<f64>,
+}
+
+impl Series {
+    //Adds a sample for every interval boundary passed up to now
+    //Spice for missed boundaries is worked back from the current spice, since sps is constant in one step
+    fn record(&mut self, range: GraphRange, now: f64, spice: f64, sps: f64) {
+        let interval = range.interval();
+        let capacity = range.capacity();
+        if self.spice.is_empty() {
+            self.push(capacity, now, spice, sps);
+            return;
+        }
+        //Skip boundaries that would just get pushed out again, matters for long offline gaps
+        let missed = ((now - self.last_time) / interval).floor();
+        if missed > capacity as f64 {
+            self.last_time += (missed - capacity as f64) * interval;
+        }
+        let mut time = self.last_time + interval;
+        while time <= now {
+            let past_spice = (spice - sps * (now - time)).max(0.0);
+            self.push(capacity, time, past_spice, sps);
+            time = self.last_time + interval;
+        }
+    }
+
+    //Adds one sample, dropping the oldest if full
+    fn push(&mut self, capacity: usize, time: f64, spice: f64, sps: f64) {
+        self.spice.push_back(spice);
+        self.sps.push_back(sps);
+        while self.spice.len() > capacity {
+            self.spice.pop_front();
+            self.sps.pop_front();
+        }
+        self.last_time = time;
+    }
+}
+
+//Rolling record of spice and sps over time, kept in the save so the graph survives restarts
+#[derive(Serialize, Deserialize, Default)]
+pub struct History {
+    //Seconds of game time recorded so far, offline time included
+    clock: f64,
+    minute: Series,
+    hour: Series,
+    day: Series,
+    markers: VecDeque<Marker>,
+}
+
+impl History {
+    //Gets the game time the history has reached
+    pub fn get_clock(&self) -> f64 {
+        self.clock
+    }
+
+    //Moves the clock forward and samples the current spice and sps
+    pub fn record(&mut self, dt: f64, spice: f64, sps: f64) {
+        self.clock += dt;
+        for range in GraphRange::ALL {
+            let clock = self.clock;
+            self.series_mut(range).record(range, clock, spice, sps);
+        }
+        //Markers older than the longest range can't be shown anymore
+        let oldest = self.clock - GraphRange::Day.span();
+        while self
+            .markers
+            .front()
+            .is_some_and(|marker| marker.time < oldest)
+        {
+            self.markers.pop_front();
+        }
+    }
+
+    //Adds a marker at the current time
+    pub fn add_marker(&mut self, label: &str) {
+        self.markers.push_back(Marker {
+            time: self.clock,
+            label: label.to_string(),
+        });
+    }
+
+    //Gets the samples in a range, oldest first
+    pub fn samples(&self, range: GraphRange) -> Vec<Sample> {
+        let series = self.series(range);
+        let len = series.spice.len();
+        series
+            .spice
+            .iter()
+            .zip(series.sps.iter())
+            .enumerate()
+            .map(|(i, (spice, sps))| Sample {
+                age: self.clock - series.last_time + (len - 1 - i) as f64 * range.interval(),
+                spice: *spice,
+                sps: *sps,
+            })
+            .collect()
+    }
+
+    //Gets markers inside a range along with how many seconds ago they happened
+    pub fn markers(&self, range: GraphRange) -> Vec<(f64, &Marker)> {
+        self.markers
+            .iter()
+            .map(|marker| (self.clock - marker.time, marker))
+            .filter(|(age, _)| *age <= range.span())
+            .collect()
+    }
+
+    fn series(&self, range: GraphRange) -> &Series {
+        match range {
+            GraphRange::Minute => &self.minute,
+            GraphRange::Hour => &self.hour,
+            GraphRange::Day => &self.day,
+        }
+    }
+
+    fn series_mut(&mut self, range: GraphRange) -> &mut Series {
+        match range {
+            GraphRange::Minute => &mut self.minute,
+            GraphRange::Hour => &mut self.hour,
+            GraphRange::Day => &mut self.day,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples_every_interval() {
+        let mut history = History::default();
+        //10 seconds at 0.5 second frames with 2 sps
+        let mut spice = 0.0;
+        for _ in 0..20 {
+            spice += 1.0;
+            history.record(0.5, spice, 2.0);
+        }
+        let minute = history.samples(GraphRange::Minute);
+        //First sample plus one per second after it
+        assert_eq!(minute.len(), 10);
+        //Newest sample was taken half a second ago
+        assert_eq!(minute.last().unwrap().spice, 19.0);
+        assert_eq!(minute.last().unwrap().age, 0.5);
+        //Hour range hasn't passed a boundary yet, so only has the first sample
+        assert_eq!(history.samples(GraphRange::Hour).len(), 1);
+    }
+
+    #[test]
+    fn test_offline_gap_fills_samples() {
+        let mut history = History::default();
+        history.record(1.0, 0.0, 10.0);
+        //Two hours offline at 10 sps
+        history.record(7200.0, 72000.0, 10.0);
+        let minute = history.samples(GraphRange::Minute);
+        assert_eq!(minute.len(), GraphRange::Minute.capacity());
+        //Samples are worked back from the end, one second apart
+        assert_eq!(minute[58].spice, 71990.0);
+        let hour = history.samples(GraphRange::Hour);
+        assert_eq!(hour.len(), GraphRange::Hour.capacity());
+        assert_eq!(hour.first().unwrap().age, 3540.0);
+    }
+
+    #[test]
+    fn test_markers_expire() {
+        let mut history = History::default();
+        history.record(1.0, 0.0, 0.0);
+        history.add_marker("Tools");
+        history.record(120.0, 0.0, 0.0);
+        assert!(history.markers(GraphRange::Minute).is_empty());
+        assert_eq!(history.markers(GraphRange::Hour)[0].0, 120.0);
+        //Past a day the marker is dropped completely
+        history.record(GraphRange::Day.span(), 0.0, 0.0);
+        assert!(history.markers(GraphRange::Day).is_empty());
+    }
+}
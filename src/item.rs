@@ -1,23 +1,98 @@
+use crate::costcurve::CostCurve;
+use crate::resources::Cost;
 use serde::{Deserialize, Serialize};
+
+//When a shop entry stops showing as "???"
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum Reveal {
+    //Shown from the start
+    #[default]
+    Always,
+    //Shown once lifetime spice reaches this fraction of the spice cost
+    Spice(f64),
+    //Shown once this many of another building are owned
+    Owns(&'static str, u32),
+}
+
+impl Reveal {
+    //Whether the rule is met, given lifetime spice, the entry's spice cost and a way to count buildings
+    pub fn is_met(&self, lifetime_spice: f64, cost: u32, owned: impl Fn(&str) -> u32) -> bool {
+        match self {
+            Reveal::Always => true,
+            Reveal::Spice(fraction) => lifetime_spice >= cost as f64 * fraction,
+            Reveal::Owns(building, count) => owned(building) >= *count,
+        }
+    }
+}
+
+//Saves from before entries could be hidden had the whole shop showing, so entries they load stay shown
+pub fn revealed_in_old_saves() -> bool {
+    true
+}
+
+//Only the name, amount owned and whether it's been revealed are saved
+//Everything else comes from the catalog when the game loads, so prices always follow the current rules
 #[derive(Serialize, Deserialize)]
 pub struct Item {
     name: String,
     amt: u32,
+    #[serde(skip)]
     worth: f64,
-    cost: u32,
+    //Price of the first one, the rest follow the curve
+    #[serde(skip)]
+    base_cost: Cost,
+    #[serde(skip)]
+    curve: CostCurve,
+    //Water made per second by each one owned
+    #[serde(skip)]
+    water: f64,
+    //Rule for showing it in the shop
+    #[serde(skip)]
+    reveal: Reveal,
+    //Whether the rule has been met, stays shown after that
+    #[serde(default = "revealed_in_old_saves")]
+    revealed: bool,
 }
 
 impl Item {
-    //Create new item (all must be hardcoded)
+    //Create new item that costs only spice (all must be hardcoded), cost is the price of the first one
     pub fn new(name: &str, amt: u32, worth: f64, cost: u32) -> Item {
+        Item::with_cost(name, amt, worth, Cost::spice(cost))
+    }
+
+    //Create new item that costs any mix of resources
+    pub fn with_cost(name: &str, amt: u32, worth: f64, cost: Cost) -> Item {
         Item {
             name: name.to_string(),
             amt,
             worth,
-            cost,
+            base_cost: cost,
+            curve: CostCurve::default(),
+            water: 0.0,
+            reveal: Reveal::Always,
+            revealed: true,
         }
     }
 
+    //Gives the item a different cost curve than the usual 15% per purchase
+    pub fn with_curve(mut self, curve: CostCurve) -> Item {
+        self.curve = curve;
+        self
+    }
+
+    //Hides the item in the shop until the rule is met
+    pub fn revealed_when(mut self, reveal: Reveal) -> Item {
+        self.reveal = reveal;
+        self.revealed = false;
+        self
+    }
+
+    //Makes the item produce water as well
+    pub fn making_water(mut self, water: f64) -> Item {
+        self.water = water;
+        self
+    }
+
     //Get name of the item
     pub fn get_name(&self) -> &str {
         &self.name
@@ -33,31 +108,91 @@ impl Item {
         self.worth
     }
 
-    //Get cost of the item
-    pub fn get_cost(&self) -> u32 {
-        self.cost
+    //Get water per second of the item
+    pub fn get_water(&self) -> f64 {
+        self.water
+    }
+
+    //Get cost of the next one, worked out from the amount owned every time so it can't drift
+    pub fn get_cost(&self) -> Cost {
+        self.price_of_nth(self.amt)
+    }
+
+    //Gets the price of the one bought when n are already owned
+    pub fn price_of_nth(&self, n: u32) -> Cost {
+        self.base_cost.scaled(self.curve.factor(n))
+    }
+
+    //Gets the price of buying count more when from are already owned
+    pub fn total_price(&self, from: u32, count: u32) -> Cost {
+        (from..from.saturating_add(count))
+            .map(|n| self.price_of_nth(n))
+            .fold(Cost::default(), |total, price| total + price)
+    }
+
+    //Get the rule for showing the item in the shop
+    pub fn get_reveal(&self) -> &Reveal {
+        &self.reveal
+    }
+
+    //Get whether the item is shown in the shop, owning any always shows it
+    pub fn is_revealed(&self) -> bool {
+        self.revealed || self.amt > 0
+    }
+
+    //Shows the item in the shop from now on
+    pub fn reveal(&mut self) {
+        self.revealed = true;
+    }
+
+    //Takes the amount owned and whether it's been revealed from a saved copy of the item
+    pub fn restore_from(&mut self, saved: &Item) {
+        self.amt = saved.amt;
+        self.revealed = saved.revealed;
     }
 
     //Get a string with item info for inventory display
     pub fn info_in_inventory(&self) -> String {
         format!(
-            "{}: Amount Owned: {}, Clicks per second: {}",
-            self.name, self.amt, self.worth
+            "{}: Amount Owned: {}, Clicks per second: {}{}",
+            self.name,
+            self.amt,
+            self.worth,
+            self.water_info()
         )
     }
 
     //Get a string with item info for shop display
     pub fn info_in_shop(&self) -> String {
         format!(
-            "{}: Cost: {}, Clicks per second: {}",
-            self.name, self.cost, self.worth
+            "{}: Cost: {}, Clicks per second: {}{}",
+            self.name,
+            self.get_cost(),
+            self.worth,
+            self.water_info()
         )
     }
 
-    //Increases the amount owned by 1 and increases the cost by 15%
+    //Extra info for items that make water, empty otherwise
+    fn water_info(&self) -> String {
+        if self.water > 0.0 {
+            format!(", Water per second: {}", self.water)
+        } else {
+            String::new()
+        }
+    }
+
+    //Removes up to count owned, returns how many were actually removed
+    //The price drops back down with the amount, so lost buildings cost the same to rebuild
+    pub fn lose(&mut self, count: u32) -> u32 {
+        let lost = count.min(self.amt);
+        self.amt -= lost;
+        lost
+    }
+
+    //Increases the amount owned by 1, which moves the cost along the curve
     pub fn purchase(&mut self) {
         self.amt += 1;
-        self.cost = (self.cost as f64 * 1.15).ceil() as u32;
     }
 }
 
@@ -70,7 +205,9 @@ mod tests {
         let test_item = Item::new("TestItem", 5, 10.0, 2);
         assert_eq!(test_item.get_amt(), 5);
         assert_eq!(test_item.get_worth(), 10.0);
-        assert_eq!(test_item.get_cost(), 2);
+        //Cost is for the next one, 2 * 1.15^5 rounded up
+        assert_eq!(test_item.get_cost(), Cost::spice(5));
+        assert_eq!(test_item.price_of_nth(0), Cost::spice(2));
     }
 
     #[test]
@@ -84,7 +221,8 @@ mod tests {
     fn test_info_in_shop() {
         let test_item = Item::new("TestItem", 2, 10.0, 2);
         let info = test_item.info_in_shop();
-        assert_eq!(info, "TestItem: Cost: 2, Clicks per second: 10");
+        //Shows the price of the third one, 2 * 1.15^2 rounded up
+        assert_eq!(info, "TestItem: Cost: 3, Clicks per second: 10");
     }
 
     #[test]
@@ -92,6 +230,93 @@ mod tests {
         let mut test_item = Item::new("TestItem", 2, 10.0, 2);
         test_item.purchase();
         assert_eq!(test_item.get_amt(), 3);
-        assert_eq!(test_item.get_cost(), 3); // 2 * 1.15.ceil() = 3
+        assert_eq!(test_item.get_cost(), Cost::spice(4)); // (2 * 1.15^3).ceil() = 4
+    }
+
+    #[test]
+    fn test_price_of_nth() {
+        let test_item = Item::new("TestItem", 0, 10.0, 100).with_curve(CostCurve::Stepwise {
+            tier_size: 2,
+            multiplier: 3.0,
+        });
+        assert_eq!(test_item.price_of_nth(0), Cost::spice(100));
+        assert_eq!(test_item.price_of_nth(1), Cost::spice(100));
+        assert_eq!(test_item.price_of_nth(2), Cost::spice(300));
+        assert_eq!(test_item.total_price(0, 4), Cost::spice(800));
+        assert_eq!(test_item.total_price(3, 0), Cost::default());
+
+        //Buying one at a time lands on the same prices
+        let mut capped = Item::new("Capped", 0, 1.0, 10).with_curve(CostCurve::Capped {
+            rate: 1.0,
+            max: 4.0,
+        });
+        let expected = capped.total_price(0, 5);
+        let mut paid = Cost::default();
+        for _ in 0..5 {
+            paid = paid + capped.get_cost();
+            capped.purchase();
+        }
+        assert_eq!(paid, expected);
+        assert_eq!(capped.get_cost(), Cost::spice(40));
+    }
+
+    #[test]
+    fn test_water_item() {
+        let cost = Cost {
+            spice: 100,
+            water: 0,
+            solari: 20,
+        };
+        let mut test_item = Item::with_cost("Deathstill", 0, 0.0, cost).making_water(0.5);
+        assert_eq!(
+            test_item.info_in_shop(),
+            "Deathstill: Cost: 100 spice + 20 solari, Clicks per second: 0, Water per second: 0.5"
+        );
+        test_item.purchase();
+        assert_eq!(test_item.get_cost().solari, 23);
+    }
+
+    #[test]
+    fn test_reveal() {
+        let test_item = Item::new("TestItem", 0, 10.0, 100).revealed_when(Reveal::Spice(0.5));
+        assert!(!test_item.is_revealed());
+        assert!(!test_item.get_reveal().is_met(49.0, 100, |_| 0));
+        assert!(test_item.get_reveal().is_met(50.0, 100, |_| 0));
+        let rule = Reveal::Owns("Fremen", 2);
+        assert!(!rule.is_met(1e9, 100, |_| 1));
+        assert!(rule.is_met(0.0, 100, |_| 2));
+        //Old saves and items outside the catalog show up right away
+        assert!(Item::new("TestItem", 0, 10.0, 100).is_revealed());
+    }
+
+    #[test]
+    fn test_saves_only_amount() {
+        let mut test_item = Item::new("TestItem", 0, 10.0, 100).making_water(1.0);
+        test_item.purchase();
+        let json = serde_json::to_string(&test_item).unwrap();
+        assert_eq!(json, r#"{"name":"TestItem","amt":1,"revealed":true}"#);
+        //Older saves had the cost and what it made, which is ignored now
+        let saved: Item =
+            serde_json::from_str(r#"{"name":"TestItem","amt":4,"worth":3.0,"cost":999}"#).unwrap();
+        let mut restored = Item::new("TestItem", 0, 10.0, 100);
+        restored.restore_from(&saved);
+        assert_eq!(restored.get_amt(), 4);
+        assert_eq!(restored.get_worth(), 10.0);
+        assert_eq!(restored.get_cost(), Cost::spice(175));
+        //They also didn't hide anything, so the entry stays visible even with a reveal rule
+        let mut restored = Item::new("TestItem", 0, 10.0, 100).revealed_when(Reveal::Spice(0.5));
+        let saved: Item = serde_json::from_str(r#"{"name":"TestItem","amt":0}"#).unwrap();
+        restored.restore_from(&saved);
+        assert!(restored.is_revealed());
+    }
+
+    #[test]
+    fn test_lose() {
+        let mut test_item = Item::new("TestItem", 3, 10.0, 2);
+        assert_eq!(test_item.lose(2), 2);
+        assert_eq!(test_item.get_amt(), 1);
+        //Can't lose more than owned
+        assert_eq!(test_item.lose(5), 1);
+        assert_eq!(test_item.get_amt(), 0);
     }
 }
@@ -0,0 +1,26 @@
+//Everything about the game that doesn't need a window, shared by the game and the simulator
+pub mod args;
+pub mod buffs;
+pub mod catalog;
+pub mod clickeritem;
+pub mod clickguard;
+pub mod clock;
+pub mod combo;
+pub mod contracts;
+pub mod costcurve;
+pub mod events;
+pub mod factions;
+pub mod history;
+pub mod item;
+pub mod market;
+pub mod replay;
+pub mod research;
+pub mod resources;
+pub mod rng;
+pub mod settings;
+pub mod simulator;
+pub mod spiceblow;
+pub mod strategy;
+pub mod synergy;
+pub mod userstate;
+pub mod weather;
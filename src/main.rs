@@ -1,15 +1,33 @@
-mod item;
-use item::Item;
-mod clickeritem;
-use clickeritem::ClickerItem;
-mod userstate;
-use serde_json::{to_writer, from_reader};
+mod effects;
+mod sound;
+mod ui;
+use clock::SimClock;
+use effects::Effects;
+use factions::{Faction, PLEDGE_SPS};
+use ggez::audio::SoundSource;
+use ggez::conf::FullscreenType;
 use ggez::graphics;
 use ggez::input::keyboard::{KeyCode, KeyInput};
 use ggez::*;
+use history::GraphRange;
+use market::OrderSide;
+use replay::{Input, Playback, Recorder};
+use rng::Rng;
+use serde_json::{from_reader, to_writer};
+use settings::{Settings, load_settings, save_settings};
+use spice_harvesting::args::flag_value;
+use spice_harvesting::strategy::format_time;
+use spice_harvesting::{
+    catalog, clock, contracts, factions, history, market, replay, rng, settings, userstate, weather,
+};
 use std::fs::File;
 use std::time::{SystemTime, UNIX_EPOCH};
-use userstate::UserState;
+use ui::{Layout, NORMAL_TEXT_SIZE, Tab, UiAction, View, clamp_scroll, paginate, split_panel_text};
+use userstate::{PRESTIGE_SPICE, UserState};
+use weather::Weather;
+
+//How many purchases "advise" plans when not given a number
+const ADVICE_COUNT: usize = 5;
 
 //I realize ggez has it's own save system, but I already had this implemented before I decided to use ggez
 // and didn't want to figure out how to change it
@@ -30,51 +48,390 @@ fn load_game() -> Option<UserState> {
     Some(user_state)
 }
 
+//Check if there's a save, if not start new game
+fn load_or_new_game() -> UserState {
+    match load_game() {
+        Some(state) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs_f64();
+            //Load offline progress
+            let duration = now - state.get_time_last_updated();
+            let mut mut_state = state;
+            //Older saves won't have anything added to the shop since
+            mut_state.sync_catalog(catalog::items(), catalog::clicker_items());
+            mut_state.catch_up(duration);
+            mut_state.set_time_last_updated(now);
+            mut_state
+        }
+        None => UserState::new(catalog::items(), catalog::clicker_items()),
+    }
+}
+
+//Plays a replay with no window as fast as possible, returns whether it ended the same way it was recorded
+fn run_headless(replay: replay::Replay) -> bool {
+    let Some((mut playback, mut user)) = Playback::new(replay) else {
+        println!("Replay's starting state couldn't be read");
+        return false;
+    };
+    playback.run_to_end(&mut user);
+    playback.matches(&user)
+}
+
 //GGEZ state struct
 struct GameState {
     user: UserState,
     input: String,
     shop_mode: bool,
+    //Panel currently shown under the spice info
+    tab: Tab,
+    //Last known mouse position, used for hover highlights and tooltips
+    mouse_pos: (f32, f32),
+    //How many panel lines are scrolled out of view above the panel
+    scroll: usize,
+    //Height of a line of text compared to its text size, measured once from the font
+    line_ratio: f32,
+    //Window and UI preferences, saved separately from the game
+    settings: Settings,
+    //How far back the graph panel shows
+    graph_range: GraphRange,
+    //Floating numbers and particles from clicks
+    effects: Effects,
+    //Latest notice from the game and how many more seconds to show it
+    notice: Option<(String, f64)>,
+    //Inputs being recorded and the file they're written to on exit, from --record
+    recorder: Option<(Recorder, String)>,
+    //Replay being watched, from --replay, player input and saving are off while it plays
+    playback: Option<Playback>,
+    //How many times normal speed the replay plays at and the clock that steps it
+    replay_speed: f64,
+    replay_clock: SimClock,
 }
 
 impl GameState {
     //Initialize game state
-    fn new(_ctx: &mut Context) -> GameResult<Self> {
-        //Check if there's a save, if not start new game
-        let user = match load_game() {
-            Some(state) => {
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs_f64();
-                //Load offline progress
-                let duration = now - state.get_time_last_updated();
-                let mut mut_state = state;
-                mut_state.update_spice(duration);
-                mut_state.set_time_last_updated(now);
-                mut_state
-            }
-            None => {
-                let items = vec![
-                    Item::new("Tools", 0, 0.1, 15),
-                    Item::new("Fremen", 0, 1.0, 100),
-                    Item::new("Ornithopter", 0, 8.0, 1100),
-                    Item::new("Spice Harvester", 0, 47.0, 12000),
-                    Item::new("Sietch", 0, 260.0, 130000),
-                ];
-                let clicker_items = vec![
-                    ClickerItem::new("CHOAM Charter", 2.0, 100),
-                    ClickerItem::new("Guild Satellite", 3.0, 500),
-                ];
-                UserState::new(items, clicker_items)
-            }
-        };
+    fn new(ctx: &mut Context, settings: Settings, user: UserState) -> GameResult<Self> {
+        //Measure a line with tall and low letters so rows don't overlap
+        let line_height = graphics::Text::new("Ag|")
+            .set_scale(NORMAL_TEXT_SIZE)
+            .measure(ctx)?
+            .y;
+        let line_ratio = (line_height / NORMAL_TEXT_SIZE).max(1.0);
         Ok(Self {
             user,
             input: String::new(),
             shop_mode: false,
+            tab: Tab::Inventory,
+            mouse_pos: (0.0, 0.0),
+            scroll: 0,
+            line_ratio,
+            settings,
+            graph_range: GraphRange::Minute,
+            effects: Effects::new(Rng::from_time()),
+            notice: None,
+            recorder: None,
+            playback: None,
+            replay_speed: 1.0,
+            replay_clock: SimClock::default(),
         })
     }
+
+    //Writes down an input if recording, call it right before doing the input
+    fn record(&mut self, input: Input) {
+        if let Some((recorder, _)) = self.recorder.as_mut() {
+            recorder.record(input);
+        }
+    }
+
+    //Gets the layout for the current window size
+    fn layout(&self, ctx: &Context) -> Layout {
+        let (w, h) = ctx.gfx.drawable_size();
+        Layout::new(w, h, self.settings.ui_scale, self.line_ratio)
+    }
+
+    //Gets what the mouse is currently over
+    fn hovered(&self, ctx: &Context) -> Option<UiAction> {
+        let (x, y) = self.mouse_pos;
+        let view = View {
+            tab: self.tab,
+            num_shop_entries: self.user.total_num_items(),
+            scroll: self.scroll,
+            event_active: self.user.get_active_event().is_some(),
+            spice_blow: self.user.get_spice_blow().map(|blow| (blow.x, blow.y)),
+            num_buffs: self.user.get_buffs().get_active().len(),
+        };
+        self.layout(ctx).hit_test(x, y, &view)
+    }
+
+    //Gets the text for the open panel
+    fn panel_text(&self) -> String {
+        match self.tab {
+            Tab::Shop => self.user.list_shop(),
+            Tab::Inventory => self.user.list_inventory(),
+            Tab::Stats => self.user.list_stats(),
+            Tab::Market => self.user.list_market(),
+            Tab::Research => self.user.list_research(),
+            Tab::Contracts => self.user.list_contracts(),
+            Tab::Graph => {
+                let samples = self.user.get_history().samples(self.graph_range);
+                let peak_spice = samples.iter().map(|s| s.spice).fold(0.0, f64::max);
+                let peak_sps = samples.iter().map(|s| s.sps).fold(0.0, f64::max);
+                format!(
+                    "---Graph: last {}---\nSpice (orange) peak: {:.2}, Spice per second (blue) peak: {:.2}\n",
+                    self.graph_range.label().to_ascii_lowercase(),
+                    peak_spice,
+                    peak_sps
+                )
+            }
+        }
+    }
+
+    //Plays the chime for reaching a goal, the game carries on without it if there's no sound device
+    fn play_goal_chime(&self, ctx: &Context) {
+        let data = audio::SoundData::from_bytes(&sound::goal_chime());
+        let played =
+            audio::Source::from_data(ctx, data).and_then(|mut chime| chime.play_detached(ctx));
+        if played.is_err() {
+            println!("Unable to play the goal sound");
+        }
+    }
+
+    //Harvests spice and shows click feedback at the given position
+    fn harvest(&mut self, x: f32, y: f32) {
+        self.record(Input::Click);
+        let amount = self.user.update_spice_by_click();
+        //Clicks over the rate cap give nothing, so there's nothing to show
+        if self.settings.effects_enabled && amount > 0.0 {
+            self.effects.spawn_click(x, y, amount);
+        }
+    }
+
+    //Draws the last hour of spice prices with the mean price as a dashed guide
+    fn draw_market(
+        &self,
+        ctx: &Context,
+        canvas: &mut graphics::Canvas,
+        layout: &Layout,
+    ) -> GameResult {
+        let area = layout.graph_area();
+        if area.w < 1.0 || area.h < 1.0 {
+            return Ok(());
+        }
+        let frame = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(1.0),
+            area,
+            graphics::Color::from_rgb(90, 60, 30),
+        )?;
+        canvas.draw(&frame, graphics::DrawParam::default());
+
+        //Scaled so the mean sits in the middle unless prices ran further than that
+        let prices = self.user.get_market().get_history();
+        let peak = prices
+            .iter()
+            .copied()
+            .fold(market::MEAN_PRICE * 2.0, f64::max);
+        let y_at = |price: f64| area.bottom() - (price / peak) as f32 * area.h;
+        let mean_y = y_at(market::MEAN_PRICE);
+        let dash = 8.0 * layout.scale;
+        let mut x = area.x;
+        while x < area.right() {
+            let line = graphics::Mesh::new_line(
+                ctx,
+                &[
+                    ggez::glam::Vec2::new(x, mean_y),
+                    ggez::glam::Vec2::new((x + dash).min(area.right()), mean_y),
+                ],
+                1.0,
+                graphics::Color::from_rgb(120, 120, 120),
+            )?;
+            canvas.draw(&line, graphics::DrawParam::default());
+            x += dash * 2.0;
+        }
+
+        //Polylines need at least two points
+        if prices.len() < 2 {
+            return Ok(());
+        }
+        //Newest price on the right edge, one tick per step back
+        let step = area.w / (prices.len() - 1) as f32;
+        let points: Vec<ggez::glam::Vec2> = prices
+            .iter()
+            .enumerate()
+            .map(|(i, price)| ggez::glam::Vec2::new(area.x + i as f32 * step, y_at(*price)))
+            .collect();
+        let mesh =
+            graphics::Mesh::new_line(ctx, &points, 2.0, graphics::Color::from_rgb(120, 200, 120))?;
+        canvas.draw(&mesh, graphics::DrawParam::default());
+        Ok(())
+    }
+
+    //Draws click particles and floating numbers
+    fn draw_effects(
+        &self,
+        ctx: &Context,
+        canvas: &mut graphics::Canvas,
+        layout: &Layout,
+    ) -> GameResult {
+        //All particles go in one mesh so a big burst is still one draw call
+        let particles = self.effects.particles();
+        if !particles.is_empty() {
+            let size = 4.0 * layout.scale;
+            let mut builder = graphics::MeshBuilder::new();
+            for particle in particles {
+                let color = if particle.spice {
+                    graphics::Color::new(0.9, 0.55, 0.15, particle.alpha())
+                } else {
+                    graphics::Color::new(0.85, 0.75, 0.5, particle.alpha())
+                };
+                builder.rectangle(
+                    graphics::DrawMode::fill(),
+                    graphics::Rect::new(particle.x, particle.y, size, size),
+                    color,
+                )?;
+            }
+            let mesh = graphics::Mesh::from_data(ctx, builder.build());
+            canvas.draw(&mesh, graphics::DrawParam::default());
+        }
+        for label in self.effects.labels() {
+            canvas.draw(
+                graphics::Text::new(label.text.as_str()).set_scale(layout.normal_text),
+                graphics::DrawParam::default()
+                    .dest(ggez::glam::Vec2::new(label.x, label.y))
+                    .color(graphics::Color::new(1.0, 0.8, 0.4, label.alpha())),
+            );
+        }
+        Ok(())
+    }
+
+    //Draws spice and sps lines for the selected range, with a line at each purchase
+    fn draw_graph(
+        &self,
+        ctx: &Context,
+        canvas: &mut graphics::Canvas,
+        layout: &Layout,
+    ) -> GameResult {
+        let area = layout.graph_area();
+        if area.w < 1.0 || area.h < 1.0 {
+            return Ok(());
+        }
+        let history = self.user.get_history();
+        let samples = history.samples(self.graph_range);
+        let span = self.graph_range.span();
+        let frame = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(1.0),
+            area,
+            graphics::Color::from_rgb(90, 60, 30),
+        )?;
+        canvas.draw(&frame, graphics::DrawParam::default());
+
+        //Newest samples on the right, anything older than the range is off the left edge
+        let x_at = |age: f64| area.right() - (age / span) as f32 * area.w;
+        for (age, marker) in history.markers(self.graph_range) {
+            let x = x_at(age);
+            let line = graphics::Mesh::new_line(
+                ctx,
+                &[
+                    ggez::glam::Vec2::new(x, area.y),
+                    ggez::glam::Vec2::new(x, area.bottom()),
+                ],
+                1.0,
+                graphics::Color::from_rgb(120, 120, 120),
+            )?;
+            canvas.draw(&line, graphics::DrawParam::default());
+            canvas.draw(
+                graphics::Text::new(marker.label.as_str()).set_scale(layout.small_text * 0.6),
+                ggez::glam::Vec2::new(x + 2.0, area.y + 2.0),
+            );
+        }
+
+        //Each line is scaled to its own peak so sps is still visible next to a big spice count
+        let lines = [
+            (
+                samples.iter().map(|s| (s.age, s.spice)).collect::<Vec<_>>(),
+                graphics::Color::from_rgb(230, 140, 40),
+            ),
+            (
+                samples.iter().map(|s| (s.age, s.sps)).collect::<Vec<_>>(),
+                graphics::Color::from_rgb(80, 160, 230),
+            ),
+        ];
+        for (points, color) in lines.iter() {
+            //Polylines need at least two points
+            if points.len() < 2 {
+                continue;
+            }
+            let peak = points.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+            let peak = if peak > 0.0 { peak } else { 1.0 };
+            let mesh_points: Vec<ggez::glam::Vec2> = points
+                .iter()
+                .map(|(age, value)| {
+                    let y = area.bottom() - (value / peak) as f32 * area.h;
+                    ggez::glam::Vec2::new(x_at(*age), y)
+                })
+                .collect();
+            let mesh = graphics::Mesh::new_line(ctx, &mesh_points, 2.0, *color)?;
+            canvas.draw(&mesh, graphics::DrawParam::default());
+        }
+        Ok(())
+    }
+
+    //Switches panels, starting the new one scrolled to the top
+    fn set_tab(&mut self, tab: Tab) {
+        if self.tab != tab {
+            self.tab = tab;
+            self.scroll = 0;
+        }
+    }
+
+    //Scrolls the open panel by some number of lines, negative is up
+    fn scroll_by(&mut self, ctx: &Context, lines: isize) {
+        let text = self.panel_text();
+        let (_, body) = split_panel_text(&text);
+        let visible = self.layout(ctx).visible_rows();
+        let scroll = self.scroll.saturating_add_signed(lines);
+        self.scroll = clamp_scroll(scroll, body.len(), visible);
+    }
+
+    //Opens a panel at the given page and prints that page to the terminal
+    fn show_page(&mut self, ctx: &Context, tab: Tab, page: usize) {
+        self.set_tab(tab);
+        let text = self.panel_text();
+        let (header, body) = split_panel_text(&text);
+        let per_page = self.layout(ctx).visible_rows();
+        let (lines, total_pages) = paginate(&body, page, per_page);
+        let page = page.clamp(1, total_pages);
+        println!("{} (Page {} of {})", header, page, total_pages);
+        for line in lines {
+            println!("{}", line);
+        }
+        self.scroll = clamp_scroll((page - 1) * per_page.max(1), body.len(), per_page);
+    }
+
+    //Gets the hover text for whatever the mouse is over
+    fn tooltip(&self, ctx: &Context) -> Option<String> {
+        match self.hovered(ctx)? {
+            UiAction::Harvest => Some(format!(
+                "Click to harvest {:.2} spice",
+                self.user.get_spc() * self.user.get_combo()
+            )),
+            UiAction::BuyShopEntry(entry) => self.user.shop_tooltip(entry),
+            UiAction::Evacuate => Some(String::from("Pull your crews out before the worm arrives")),
+            UiAction::CollectSpiceBlow => Some(String::from("Spice blow! Click to collect")),
+            UiAction::BuffIcon(i) => {
+                let buff = self.user.get_buffs().get_active().get(i)?;
+                Some(format!(
+                    "{}\n{}\n{:.0} seconds left",
+                    buff.name,
+                    buff.describe(),
+                    buff.time_left.ceil()
+                ))
+            }
+            UiAction::SelectTab(_) | UiAction::SelectRange(_) => None,
+        }
+    }
 }
 
 //GGEZ event handler implementation
@@ -82,62 +439,422 @@ impl ggez::event::EventHandler for GameState {
     //Per-frame update
     fn update(&mut self, ctx: &mut Context) -> GameResult {
         let dt = ctx.time.delta().as_secs_f64();
-        //Update spice every frame based on sps
-        self.user.update_spice(dt);
+        //A replay runs the game itself, at whatever speed was asked for
+        if let Some(playback) = self.playback.as_mut() {
+            if !playback.is_finished() {
+                let steps = self.replay_clock.advance(dt * self.replay_speed);
+                playback.advance(&mut self.user, steps as u64);
+                if playback.is_finished() {
+                    if playback.matches(&self.user) {
+                        println!("Replay finished, final state matches the recording");
+                    } else {
+                        println!("Replay finished, final state does NOT match the recording");
+                    }
+                }
+            }
+        //Run the game for however long the frame took, it moves in fixed steps inside
+        } else if let Some((recorder, _)) = self.recorder.as_mut() {
+            recorder.update(&mut self.user, dt);
+        } else {
+            self.user.update(dt);
+        }
+        self.effects.update(dt as f32);
+        if self.user.take_goal_reached() && self.settings.goal_sound {
+            self.play_goal_chime(ctx);
+        }
+        //Show the newest notice for a few seconds
+        if let Some(notice) = self.user.take_notices().pop() {
+            self.notice = Some((notice, 5.0));
+        }
+        if let Some((_, time_left)) = self.notice.as_mut() {
+            *time_left -= dt;
+            if *time_left <= 0.0 {
+                self.notice = None;
+            }
+        }
         Ok(())
     }
     //Drawing text is based on ggez examples hello_world.rs
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         //Create all black canvas
         let mut canvas = graphics::Canvas::from_frame(ctx, graphics::Color::from_rgb(0, 0, 0));
-        let offset = 32.0;
-        let top_text_size = 48.0;
-        let normal_text_size = 32.0;
+        let layout = self.layout(ctx);
+        //Panel text is needed first so scroll is valid before checking what's hovered
+        let panel_text = self.panel_text();
+        let (header, body) = split_panel_text(&panel_text);
+        let visible = layout.visible_rows();
+        self.scroll = clamp_scroll(self.scroll, body.len(), visible);
+        let hovered = self.hovered(ctx);
 
-        //Drawing the spice and sps info
-        let spice_text = format!(
+        //Drawing the spice and sps info, with the combo next to spice per click while one is going
+        let mut spice_text = format!(
             "Spice: {:.2}\nSpice per second: {:.2}\nSpice per click: {:.2}",
             self.user.get_spice(),
             self.user.get_sps(),
             self.user.get_spc()
         );
+        if self.user.get_combo() > 1.0 {
+            spice_text.push_str(&format!("   Combo x{:.2}", self.user.get_combo()));
+        }
         //Drawing spice info at top left corner
-        let spice_pos = ggez::glam::Vec2::new(offset, offset);
+        let spice_pos = ggez::glam::Vec2::new(layout.prompt.0, layout.harvest.y);
         //Text set a little bigger since it's important info
         canvas.draw(
-            graphics::Text::new(spice_text).set_scale(top_text_size),
+            graphics::Text::new(spice_text).set_scale(layout.top_text),
             spice_pos,
         );
 
-        //Drawing the shop/inventory info
-        let middle_y = offset + (top_text_size * 3.0) + 8.0; //Sets text to be a little under the spice info
-        let middle_pos = ggez::glam::Vec2::new(offset, middle_y);
-        //Draw either shop or inventory based on mode
-        if self.shop_mode {
-            let shop_text = self.user.list_shop();
+        //Water, solari and melange quality on a smaller line under the spice info
+        let resources_text = format!(
+            "Water: {:.2} (+{:.2}/s)   Solari: {:.2}   Quality: {:.0}%",
+            self.user.get_water(),
+            self.user.water_per_second(),
+            self.user.get_solari(),
+            self.user.melange_quality() * 100.0
+        );
+        canvas.draw(
+            graphics::Text::new(resources_text).set_scale(layout.small_text),
+            graphics::DrawParam::default()
+                .dest(ggez::glam::Vec2::new(
+                    layout.resources.0,
+                    layout.resources.1,
+                ))
+                .color(graphics::Color::from_rgb(170, 210, 230)),
+        );
+
+        //Progress towards the spice goal under the Harvest button, with how long is left
+        let bar = layout.goal_bar;
+        if let Some(progress) = self.user.goal_progress()
+            && bar.w >= 1.0
+            && bar.h >= 1.0
+        {
+            let filled = bar.w * progress as f32;
+            if filled >= 1.0 {
+                let fill = graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    graphics::Rect::new(bar.x, bar.y, filled, bar.h),
+                    graphics::Color::from_rgb(120, 70, 20),
+                )?;
+                canvas.draw(&fill, graphics::DrawParam::default());
+            }
+            let outline = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::stroke(1.0),
+                bar,
+                graphics::Color::from_rgb(190, 100, 20),
+            )?;
+            canvas.draw(&outline, graphics::DrawParam::default());
+            let eta = self
+                .user
+                .goal_eta()
+                .map_or(String::new(), |eta| format!(", {} left", format_time(eta)));
             canvas.draw(
-                graphics::Text::new(shop_text).set_scale(normal_text_size),
-                middle_pos,
+                graphics::Text::new(format!("Goal {:.0}%{}", progress * 100.0, eta))
+                    .set_scale(layout.small_text * 0.9),
+                ggez::glam::Vec2::new(bar.x + 4.0 * layout.scale, bar.y),
             );
+        }
+
+        //Harvest button, lighter when hovered
+        let harvest_color = if hovered == Some(UiAction::Harvest) {
+            graphics::Color::from_rgb(230, 140, 40)
         } else {
-            let inventory_text = self.user.list_inventory();
+            graphics::Color::from_rgb(190, 100, 20)
+        };
+        let harvest_mesh = graphics::Mesh::new_rounded_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            layout.harvest,
+            12.0 * layout.scale,
+            harvest_color,
+        )?;
+        canvas.draw(&harvest_mesh, graphics::DrawParam::default());
+        let harvest_label = ggez::glam::Vec2::new(
+            layout.harvest.x + 24.0 * layout.scale,
+            layout.harvest.y + (layout.harvest.h - layout.top_text) / 2.0,
+        );
+        canvas.draw(
+            graphics::Text::new("Harvest").set_scale(layout.top_text),
+            harvest_label,
+        );
+        //Current weather and how long until it changes along the bottom of the button
+        let weather = self.user.get_weather();
+        let weather_left = weather.get_time_left().ceil() as u64;
+        let weather_color = match weather.current() {
+            Weather::Calm => graphics::Color::from_rgb(250, 230, 170),
+            Weather::CoriolisStorm => graphics::Color::from_rgb(150, 200, 255),
+        };
+        let weather_pos = ggez::glam::Vec2::new(
+            layout.harvest.x + 12.0 * layout.scale,
+            layout.harvest.bottom() - layout.small_text - 8.0 * layout.scale,
+        );
+        canvas.draw(
+            graphics::Text::new(format!(
+                "{} {}:{:02}",
+                weather.current().name(),
+                weather_left / 60,
+                weather_left % 60
+            ))
+            .set_scale(layout.small_text),
+            graphics::DrawParam::default()
+                .dest(weather_pos)
+                .color(weather_color),
+        );
+
+        //Tab buttons, the open tab is filled in
+        for (tab, rect) in Tab::ALL.iter().zip(layout.tabs.iter()) {
+            let mode = if *tab == self.tab {
+                graphics::DrawMode::fill()
+            } else {
+                graphics::DrawMode::stroke(2.0)
+            };
+            let tab_mesh = graphics::Mesh::new_rectangle(
+                ctx,
+                mode,
+                *rect,
+                graphics::Color::from_rgb(90, 60, 30),
+            )?;
+            canvas.draw(&tab_mesh, graphics::DrawParam::default());
+            let label_pos =
+                ggez::glam::Vec2::new(rect.x + 12.0 * layout.scale, rect.y + 8.0 * layout.scale);
             canvas.draw(
-                graphics::Text::new(inventory_text).set_scale(normal_text_size),
-                middle_pos,
+                graphics::Text::new(tab.label()).set_scale(layout.small_text),
+                label_pos,
             );
         }
 
+        //Drawing the open panel one line at a time so rows line up with click regions
+        //The best value building gets a green row so it stands out while shopping
+        if self.tab == Tab::Shop
+            && let Some(best) = self.user.best_value()
+            && (self.scroll..self.scroll + visible).contains(&best)
+        {
+            let highlight = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                layout.panel_row(best - self.scroll + 1),
+                graphics::Color::from_rgb(25, 60, 30),
+            )?;
+            canvas.draw(&highlight, graphics::DrawParam::default());
+        }
+        if let Some(UiAction::BuyShopEntry(entry)) = hovered {
+            let highlight = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                layout.panel_row(entry - self.scroll + 1),
+                graphics::Color::from_rgb(50, 35, 20),
+            )?;
+            canvas.draw(&highlight, graphics::DrawParam::default());
+        }
+        //Clip to the panel so long lines don't run over anything else
+        let clip = layout.panel;
+        if clip.w >= 1.0 && clip.h >= 1.0 {
+            canvas.set_scissor_rect(clip)?;
+        }
+        let header_rect = layout.panel_row(0);
+        canvas.draw(
+            graphics::Text::new(header).set_scale(layout.normal_text),
+            ggez::glam::Vec2::new(header_rect.x, header_rect.y),
+        );
+        for (row, line) in body.iter().skip(self.scroll).take(visible).enumerate() {
+            let rect = layout.panel_row(row + 1);
+            canvas.draw(
+                graphics::Text::new(*line).set_scale(layout.normal_text),
+                ggez::glam::Vec2::new(rect.x, rect.y),
+            );
+        }
+        canvas.set_default_scissor_rect();
+        //Graph panel draws the graph and its range buttons under the legend
+        if self.tab == Tab::Graph {
+            for (range, rect) in GraphRange::ALL.iter().zip(layout.graph_buttons().iter()) {
+                let mode = if *range == self.graph_range {
+                    graphics::DrawMode::fill()
+                } else {
+                    graphics::DrawMode::stroke(2.0)
+                };
+                let button = graphics::Mesh::new_rectangle(
+                    ctx,
+                    mode,
+                    *rect,
+                    graphics::Color::from_rgb(90, 60, 30),
+                )?;
+                canvas.draw(&button, graphics::DrawParam::default());
+                canvas.draw(
+                    graphics::Text::new(range.label()).set_scale(layout.small_text),
+                    ggez::glam::Vec2::new(rect.x + 8.0 * layout.scale, rect.y),
+                );
+            }
+            self.draw_graph(ctx, &mut canvas, &layout)?;
+        }
+        if self.tab == Tab::Market {
+            self.draw_market(ctx, &mut canvas, &layout)?;
+        }
+        //Scroll bar on the right edge when there's more than fits
+        if body.len() > visible && visible > 0 {
+            let track_top = layout.panel_row(1).y;
+            let track_h = visible as f32 * layout.row_height;
+            let bar_h = track_h * visible as f32 / body.len() as f32;
+            let bar_y = track_top + track_h * self.scroll as f32 / body.len() as f32;
+            let bar = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(layout.panel.right() - 6.0, bar_y, 6.0, bar_h),
+                graphics::Color::from_rgb(120, 80, 40),
+            )?;
+            canvas.draw(&bar, graphics::DrawParam::default());
+        }
+
         //Draw command prompt
-        let (_w, h) = ctx.gfx.drawable_size(); //Get height of game screen
-        let bot_y = h - offset - normal_text_size; //Y one line up from bottom
-        let bot_pos = ggez::glam::Vec2::new(offset, bot_y);
+        let bot_pos = ggez::glam::Vec2::new(layout.prompt.0, layout.prompt.1);
         //Draw different input prompt text based on mode
         if self.shop_mode {
             let input_text = format!("Purchase Item Number: {}", self.input);
-            canvas.draw(graphics::Text::new(input_text).set_scale(32.), bot_pos);
+            canvas.draw(
+                graphics::Text::new(input_text).set_scale(layout.normal_text),
+                bot_pos,
+            );
         } else {
             let input_text = format!("Enter Command: {}", self.input);
-            canvas.draw(graphics::Text::new(input_text).set_scale(32.), bot_pos);
+            canvas.draw(
+                graphics::Text::new(input_text).set_scale(layout.normal_text),
+                bot_pos,
+            );
+        }
+
+        //Random event banner covers the top of the panel until it's dealt with
+        if let Some(event) = self.user.get_active_event() {
+            let banner = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                layout.event_banner(),
+                graphics::Color::from_rgb(140, 20, 20),
+            )?;
+            canvas.draw(&banner, graphics::DrawParam::default());
+            let warning = format!("{} ({:.0}s)", event.kind.warning(), event.time_left.ceil());
+            canvas.draw(
+                graphics::Text::new(warning).set_scale(layout.small_text),
+                ggez::glam::Vec2::new(
+                    layout.event_banner().x + 8.0 * layout.scale,
+                    layout.event_banner().y + 8.0 * layout.scale,
+                ),
+            );
+            let evacuate = layout.evacuate_button();
+            let button_color = if hovered == Some(UiAction::Evacuate) {
+                graphics::Color::from_rgb(230, 140, 40)
+            } else {
+                graphics::Color::from_rgb(190, 100, 20)
+            };
+            let button = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                evacuate,
+                button_color,
+            )?;
+            canvas.draw(&button, graphics::DrawParam::default());
+            canvas.draw(
+                graphics::Text::new("Evacuate").set_scale(layout.small_text),
+                ggez::glam::Vec2::new(evacuate.x + 8.0 * layout.scale, evacuate.y),
+            );
+        }
+
+        //Running buffs as icons next to the tabs, shading fills in as they run out
+        let buffs = self.user.get_buffs().get_active();
+        for (buff, rect) in buffs.iter().zip(layout.buff_icons(buffs.len())) {
+            let color = if buff.is_debuff() {
+                graphics::Color::from_rgb(150, 40, 40)
+            } else {
+                graphics::Color::from_rgb(50, 130, 60)
+            };
+            let icon = graphics::Mesh::new_rounded_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                rect,
+                6.0 * layout.scale,
+                color,
+            )?;
+            canvas.draw(&icon, graphics::DrawParam::default());
+            let used = rect.h * (1.0 - buff.fraction_left() as f32);
+            if used >= 1.0 {
+                let shade = graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    graphics::Rect::new(rect.x, rect.y, rect.w, used),
+                    graphics::Color::new(0.0, 0.0, 0.0, 0.5),
+                )?;
+                canvas.draw(&shade, graphics::DrawParam::default());
+            }
+            //First letter of each word in the name, like CF for Click Frenzy
+            let initials: String = buff
+                .name
+                .split_whitespace()
+                .filter_map(|word| word.chars().next())
+                .collect();
+            canvas.draw(
+                graphics::Text::new(initials).set_scale(rect.h * 0.5),
+                ggez::glam::Vec2::new(rect.x + 3.0 * layout.scale, rect.y + 1.0 * layout.scale),
+            );
+            canvas.draw(
+                graphics::Text::new(format!("{:.0}", buff.time_left.ceil()))
+                    .set_scale(rect.h * 0.4),
+                ggez::glam::Vec2::new(rect.x + 3.0 * layout.scale, rect.bottom() - rect.h * 0.4),
+            );
+        }
+
+        //Spice blow pulses so it stands out from the rest of the screen
+        if let Some(blow) = self.user.get_spice_blow() {
+            let rect = layout.spice_blow(blow.x, blow.y);
+            let pulse = (ctx.time.time_since_start().as_secs_f32() * 6.0).sin() * 0.1 + 0.9;
+            let blow_mesh = graphics::Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::fill(),
+                rect.center(),
+                rect.w / 2.0 * pulse,
+                0.5,
+                graphics::Color::from_rgb(240, 150, 30),
+            )?;
+            canvas.draw(&blow_mesh, graphics::DrawParam::default());
+            canvas.draw(
+                graphics::Text::new(format!("{:.0}", blow.time_left.ceil()))
+                    .set_scale(layout.small_text),
+                ggez::glam::Vec2::new(
+                    rect.center().x - layout.small_text / 4.0,
+                    rect.center().y - layout.small_text / 2.0,
+                ),
+            );
+        }
+
+        //Latest notice goes on the right of the prompt line
+        if let Some((notice, _)) = &self.notice {
+            let mut notice_text = graphics::Text::new(notice.as_str());
+            notice_text.set_scale(layout.small_text);
+            let size = notice_text.measure(ctx)?;
+            canvas.draw(
+                &notice_text,
+                ggez::glam::Vec2::new(layout.panel.right() - size.x, layout.prompt.1),
+            );
+        }
+
+        //Click effects go over the panels but under the tooltip
+        self.draw_effects(ctx, &mut canvas, &layout)?;
+
+        //Tooltip drawn last so it's on top of everything
+        if let Some(tooltip) = self.tooltip(ctx) {
+            let mut tooltip_text = graphics::Text::new(tooltip);
+            tooltip_text.set_scale(layout.small_text);
+            let size = tooltip_text.measure(ctx)?;
+            let (w, h) = ctx.gfx.drawable_size();
+            //Keep the tooltip inside the window
+            let x = (self.mouse_pos.0 + 16.0).min(w - size.x - 8.0);
+            let y = (self.mouse_pos.1 + 16.0).min(h - size.y - 8.0);
+            let background = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(x - 6.0, y - 6.0, size.x + 12.0, size.y + 12.0),
+                graphics::Color::from_rgb(40, 40, 40),
+            )?;
+            canvas.draw(&background, graphics::DrawParam::default());
+            canvas.draw(&tooltip_text, ggez::glam::Vec2::new(x, y));
         }
 
         canvas.finish(ctx)?;
@@ -156,6 +873,13 @@ impl ggez::event::EventHandler for GameState {
 
     //Using this doc.rs as an example: https://docs.rs/ggez/latest/ggez/input/keyboard/index.html
     fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, _repeat: bool) -> GameResult {
+        //Watching a replay, escape leaves without saving and nothing else can change the game
+        if self.playback.is_some() {
+            if input.keycode == Some(KeyCode::Escape) {
+                ctx.request_quit();
+            }
+            return Ok(());
+        }
         //Using matching to handle different keys
         match input.keycode {
             //If escape, save and quit
@@ -169,23 +893,21 @@ impl ggez::event::EventHandler for GameState {
                 let cmd = self.input.trim().to_ascii_lowercase();
                 //Shop mode commands
                 if self.shop_mode {
-                    //If in shop mode try to get input number, if not valid exit shop
-                    let item_num = cmd.parse::<usize>();
-                    match item_num {
-                        Ok(i) => {
-                            let total_items = self.user.total_num_items();
-                            if i == 0 || i > total_items {
-                                //If invalid item number
+                    //If in shop mode try to get input number and how many, if not valid exit shop
+                    match parse_shop_input(&cmd) {
+                        Some((i, count)) => {
+                            //Shop is numbered from 1, items listed before clicker items
+                            if i > 0 {
+                                self.record(Input::Buy {
+                                    entry: i - 1,
+                                    count,
+                                });
+                            }
+                            if i == 0 || !self.user.buy_shop_entries(i - 1, count) {
                                 println!("Invalid item number");
-                            } else if i > self.user.num_items() {
-                                //If it's a clicker item (Items are listed first)
-                                self.user.buy_clicker_item(i - self.user.num_items() - 1);
-                            } else {
-                                //It's a normal item
-                                self.user.buy_item(i - 1);
                             }
                         }
-                        Err(_) => {
+                        None => {
                             println!("Exited shop.");
                         }
                     }
@@ -194,7 +916,9 @@ impl ggez::event::EventHandler for GameState {
                 //Normal mode commands
                 //If empty input, treat as click (on the off chance the user has no mouse)
                 } else if cmd.is_empty() {
-                    self.user.update_spice_by_click();
+                    //No cursor to show feedback at, so use the middle of the harvest button
+                    let harvest = self.layout(ctx).harvest;
+                    self.harvest(harvest.center().x, harvest.center().y);
                 //If "save" command, save game
                 } else if cmd == "save" {
                     save_game(&mut self.user);
@@ -206,6 +930,197 @@ impl ggez::event::EventHandler for GameState {
                 //If "shop" command, enter shop mode
                 } else if cmd == "shop" {
                     self.shop_mode = true;
+                    self.set_tab(Tab::Shop);
+                //"inventory" and "stats" switch the panel without the mouse
+                } else if cmd == "inventory" {
+                    self.set_tab(Tab::Inventory);
+                } else if cmd == "stats" {
+                    self.set_tab(Tab::Stats);
+                //"graph" opens the graph, "graph hour" also picks the range
+                } else if cmd == "graph" {
+                    self.set_tab(Tab::Graph);
+                } else if let Some(range) = cmd.strip_prefix("graph ") {
+                    match GraphRange::ALL
+                        .iter()
+                        .find(|r| r.label().eq_ignore_ascii_case(range.trim()))
+                    {
+                        Some(range) => {
+                            self.graph_range = *range;
+                            self.set_tab(Tab::Graph);
+                        }
+                        None => println!("Graph range must be minute, hour or day"),
+                    }
+                //"<panel> <page>" opens that page of a panel and prints it
+                } else if let Some((tab, page)) = parse_page_command(&cmd) {
+                    self.show_page(ctx, tab, page);
+                //"evacuate" responds to a sandworm warning without the mouse
+                } else if cmd == "evacuate" {
+                    self.record(Input::Evacuate);
+                    if !self.user.evacuate() {
+                        println!("Nothing to evacuate from");
+                    }
+                //"market" opens the market panel and prints any limit orders
+                } else if cmd == "market" {
+                    self.set_tab(Tab::Market);
+                    print!("{}", self.user.list_market());
+                    for order in self.user.get_market().get_orders() {
+                        println!("Limit order: {}", order.describe());
+                    }
+                //"sell spice <amount>" and "buy spice <amount>" trade with CHOAM right away
+                //Adding "at <price>" places a limit order instead
+                } else if cmd.starts_with("sell spice") || cmd.starts_with("buy spice") {
+                    match parse_trade_command(&cmd) {
+                        Some((_, _, Some(_))) if !self.user.is_unlocked(catalog::LIMIT_ORDERS) => {
+                            println!("Limit orders need CHOAM Futures to be researched first");
+                        }
+                        Some((side, amount, Some(price))) => {
+                            self.record(Input::PlaceOrder {
+                                side,
+                                amount,
+                                price,
+                            });
+                            if self.user.place_order(side, amount, price) {
+                                println!("Limit order placed");
+                            } else {
+                                println!("Amount and price must be more than 0");
+                            }
+                        }
+                        Some((OrderSide::Sell, amount, None)) => {
+                            self.record(Input::SellSpice(amount));
+                            if !self.user.sell_spice(amount) {
+                                println!("Not enough spice to sell");
+                            }
+                        }
+                        Some((OrderSide::Buy, amount, None)) => {
+                            self.record(Input::BuySpice(amount));
+                            if !self.user.buy_spice(amount) {
+                                println!("Not enough solari to buy that much spice");
+                            }
+                        }
+                        None => println!(
+                            "Usage: sell spice <amount> or buy spice <amount>, optionally followed by at <price>"
+                        ),
+                    }
+                //"research" opens the research panel, "research <technology>" starts one
+                } else if cmd == "research" {
+                    self.set_tab(Tab::Research);
+                    print!("{}", self.user.list_research());
+                } else if let Some(name) = cmd.strip_prefix("research ") {
+                    self.record(Input::Research(name.to_string()));
+                    self.user.start_research(name);
+                //"advise" lists the next purchases that pay back soonest, "advise <number>" plans that many
+                } else if cmd == "advise" {
+                    print!("{}", self.user.list_advice(ADVICE_COUNT));
+                } else if let Some(count) = cmd.strip_prefix("advise ") {
+                    match count.trim().parse::<usize>() {
+                        Ok(count) if count >= 1 => print!("{}", self.user.list_advice(count)),
+                        _ => println!("Number of purchases must be 1 or more"),
+                    }
+                //"goal <amount>" sets a spice goal, "goal" shows how it's going and "goal clear" drops it
+                //"goal sound on" or "goal sound off" picks whether reaching one plays a chime
+                } else if cmd == "goal" {
+                    println!("{}", self.user.describe_goal());
+                } else if cmd == "goal clear" {
+                    self.record(Input::ClearGoal);
+                    if self.user.clear_goal() {
+                        println!("Goal cleared");
+                    } else {
+                        println!("No goal to clear");
+                    }
+                } else if cmd == "goal sound on" || cmd == "goal sound off" {
+                    self.settings.goal_sound = cmd == "goal sound on";
+                    save_settings(&self.settings);
+                } else if let Some(amount) = cmd.strip_prefix("goal ") {
+                    match amount.trim().parse::<f64>() {
+                        Ok(amount) => {
+                            self.record(Input::SetGoal(amount));
+                            if self.user.set_goal(amount) {
+                                println!("{}", self.user.describe_goal());
+                            } else {
+                                println!("Goal must be more than the spice you have");
+                            }
+                        }
+                        Err(_) => println!("Goal must be a number of spice"),
+                    }
+                //"contracts" opens the contract board, "accept <number>" takes an offer on it
+                } else if cmd == "contracts" {
+                    self.set_tab(Tab::Contracts);
+                    print!("{}", self.user.list_contracts());
+                } else if let Some(number) = cmd.strip_prefix("accept ") {
+                    match number.trim().parse::<usize>() {
+                        Ok(number) if number >= 1 => {
+                            self.record(Input::Accept(number - 1));
+                            if self.user.accept_contract(number - 1) {
+                                println!("Contract accepted");
+                                self.set_tab(Tab::Contracts);
+                            } else {
+                                println!(
+                                    "No offer {} or already running {} contracts",
+                                    number,
+                                    contracts::MAX_ACTIVE
+                                );
+                            }
+                        }
+                        _ => println!("Offer number must be 1 or more"),
+                    }
+                //"cancel orders" drops every limit order
+                } else if cmd == "cancel orders" {
+                    self.record(Input::CancelOrders);
+                    println!("Cancelled {} limit orders", self.user.cancel_orders());
+                //"pledge" lists the factions, "pledge <faction>" joins one
+                } else if cmd == "pledge" {
+                    for faction in Faction::ALL {
+                        println!("{}", faction.describe());
+                    }
+                } else if let Some(name) = cmd.strip_prefix("pledge ") {
+                    match Faction::parse(name) {
+                        Some(faction) => {
+                            self.record(Input::Pledge(faction));
+                            if !self.user.pledge(faction) {
+                                match self.user.get_faction() {
+                                    Some(current) => {
+                                        println!("Already pledged to {}", current.name())
+                                    }
+                                    None => println!(
+                                        "The factions won't listen until you make {} spice per second",
+                                        PLEDGE_SPS
+                                    ),
+                                }
+                            }
+                        }
+                        None => println!("Unknown faction"),
+                    }
+                //"prestige confirm" starts over with a permanent bonus, "prestige" explains it first
+                } else if cmd == "prestige" {
+                    println!(
+                        "Prestiging needs {} spice and resets everything but your history for a permanent 10% spice per second bonus. Your faction allegiance is reset too. Type prestige confirm to go ahead",
+                        PRESTIGE_SPICE
+                    );
+                } else if cmd == "prestige confirm" {
+                    self.record(Input::Prestige);
+                    if !self
+                        .user
+                        .prestige(catalog::items(), catalog::clicker_items())
+                    {
+                        println!("Prestiging needs {} spice", PRESTIGE_SPICE);
+                    }
+                //"effects on" or "effects off" shows or hides click feedback
+                } else if cmd == "effects on" || cmd == "effects off" {
+                    self.settings.effects_enabled = cmd == "effects on";
+                    if !self.settings.effects_enabled {
+                        self.effects.clear();
+                    }
+                    save_settings(&self.settings);
+                //"scale <amount>" changes the size of all text and buttons
+                } else if let Some(scale) = cmd.strip_prefix("scale ") {
+                    match scale.trim().parse::<f32>() {
+                        Ok(scale) => {
+                            self.settings.set_ui_scale(scale);
+                            save_settings(&self.settings);
+                            println!("UI scale set to {}", self.settings.ui_scale);
+                        }
+                        Err(_) => println!("Scale must be a number"),
+                    }
                 //Else give an error message
                 } else {
                     println!("Unknown command");
@@ -216,33 +1131,224 @@ impl ggez::event::EventHandler for GameState {
             Some(KeyCode::Back) => {
                 self.input.pop();
             }
+            //F11 toggles fullscreen
+            Some(KeyCode::F11) => {
+                self.settings.fullscreen = !self.settings.fullscreen;
+                let fullscreen_type = if self.settings.fullscreen {
+                    FullscreenType::Desktop
+                } else {
+                    FullscreenType::Windowed
+                };
+                ctx.gfx.set_fullscreen(fullscreen_type)?;
+                save_settings(&self.settings);
+            }
+            //Page keys scroll the open panel a page at a time
+            Some(KeyCode::PageUp) => {
+                let page = self.layout(ctx).visible_rows() as isize;
+                self.scroll_by(ctx, -page);
+            }
+            Some(KeyCode::PageDown) => {
+                let page = self.layout(ctx).visible_rows() as isize;
+                self.scroll_by(ctx, page);
+            }
             _ => {}
         }
         Ok(())
     }
 
-    //Clicking increases spice by 1
+    //Left clicks do whatever is under the cursor, only the harvest button gives spice
     fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: event::MouseButton,
+        x: f32,
+        y: f32,
+    ) -> Result<(), GameError> {
+        if button != event::MouseButton::Left || self.playback.is_some() {
+            return Ok(());
+        }
+        self.mouse_pos = (x, y);
+        match self.hovered(ctx) {
+            Some(UiAction::Harvest) => self.harvest(x, y),
+            Some(UiAction::SelectTab(tab)) => self.set_tab(tab),
+            Some(UiAction::BuyShopEntry(entry)) => {
+                self.record(Input::Buy { entry, count: 1 });
+                self.user.buy_shop_entry(entry);
+            }
+            Some(UiAction::SelectRange(range)) => self.graph_range = range,
+            Some(UiAction::Evacuate) => {
+                self.record(Input::Evacuate);
+                self.user.evacuate();
+            }
+            Some(UiAction::CollectSpiceBlow) => {
+                self.record(Input::CollectSpiceBlow);
+                self.user.collect_spice_blow();
+            }
+            //Buff icons only show a tooltip
+            Some(UiAction::BuffIcon(_)) | None => {}
+        }
+        Ok(())
+    }
+
+    //Track the mouse so hovering can show highlights and tooltips
+    fn mouse_motion_event(
         &mut self,
         _ctx: &mut Context,
-        _button: event::MouseButton,
-        _x: f32,
-        _y: f32,
+        x: f32,
+        y: f32,
+        _dx: f32,
+        _dy: f32,
     ) -> Result<(), GameError> {
-        self.user.update_spice_by_click();
+        self.mouse_pos = (x, y);
+        Ok(())
+    }
+
+    //Remember the windowed size so it can be restored next time
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        if !self.settings.fullscreen {
+            self.settings.window_width = width;
+            self.settings.window_height = height;
+        }
+        Ok(())
+    }
+
+    //Save window settings however the game is closed
+    fn quit_event(&mut self, ctx: &mut Context) -> Result<bool, GameError> {
+        if !self.settings.fullscreen
+            && let Ok(position) = ctx.gfx.window_position()
+        {
+            self.settings.window_x = Some(position.x);
+            self.settings.window_y = Some(position.y);
+        }
+        save_settings(&self.settings);
+        if let Some((recorder, path)) = self.recorder.take() {
+            if replay::save_replay(&path, &recorder.finish(&self.user)) {
+                println!("Replay written to {}", path);
+            } else {
+                println!("Unable to write replay to {}", path);
+            }
+        }
+        Ok(false)
+    }
+
+    //Mouse wheel scrolls the open panel, 3 lines per notch
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) -> Result<(), GameError> {
+        if y > 0.0 {
+            self.scroll_by(ctx, -3);
+        } else if y < 0.0 {
+            self.scroll_by(ctx, 3);
+        }
         Ok(())
     }
 }
 
+//Parses commands like "shop 2" into the panel and page they ask for
+fn parse_page_command(cmd: &str) -> Option<(Tab, usize)> {
+    let mut words = cmd.split_whitespace();
+    let tab = match words.next()? {
+        "shop" => Tab::Shop,
+        "inventory" => Tab::Inventory,
+        "stats" => Tab::Stats,
+        _ => return None,
+    };
+    let page = words.next()?.parse::<usize>().ok()?;
+    if words.next().is_some() {
+        return None;
+    }
+    Some((tab, page))
+}
+
+//Parses shop input like "3" or "3 x10" into an item number and how many to buy
+fn parse_shop_input(cmd: &str) -> Option<(usize, u32)> {
+    let mut parts = cmd.split_whitespace();
+    let entry = parts.next()?.parse::<usize>().ok()?;
+    let count = match parts.next() {
+        Some(count) => count.trim_start_matches('x').parse::<u32>().ok()?,
+        None => 1,
+    };
+    if parts.next().is_some() || count == 0 {
+        return None;
+    }
+    Some((entry, count))
+}
+
+//Parses commands like "sell spice 100" or "buy spice 50 at 0.04" into a side, amount and optional limit price
+fn parse_trade_command(cmd: &str) -> Option<(OrderSide, f64, Option<f64>)> {
+    let mut words = cmd.split_whitespace();
+    let side = match words.next()? {
+        "sell" => OrderSide::Sell,
+        "buy" => OrderSide::Buy,
+        _ => return None,
+    };
+    if words.next()? != "spice" {
+        return None;
+    }
+    let amount = words.next()?.parse::<f64>().ok()?;
+    let price = match words.next() {
+        Some("at") => Some(words.next()?.parse::<f64>().ok()?),
+        Some(_) => return None,
+        None => None,
+    };
+    if words.next().is_some() {
+        return None;
+    }
+    Some((side, amount, price))
+}
+
 pub fn main() {
     //Adding a command line argument to give a bunch of spice for testing
     let args: Vec<String> = std::env::args().collect();
     let debug_mode = args.iter().any(|arg| arg == "--debug");
+    //"--record <file>" writes every input to a replay file on exit
+    //"--replay <file>" plays one back instead of the save, "--headless" without a window and "--speed <n>" faster
+    let record_path = flag_value(&args, "--record");
+    let mut replay = match flag_value(&args, "--replay") {
+        Some(path) => match replay::load_replay(&path) {
+            Some(replay) => Some(replay),
+            None => {
+                println!("Unable to read replay {}", path);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    if args.iter().any(|arg| arg == "--headless") {
+        let Some(replay) = replay.take() else {
+            println!("--headless needs a replay to play, use --replay <file>");
+            std::process::exit(1);
+        };
+        if run_headless(replay) {
+            println!("Replay finished, final state matches the recording");
+            return;
+        }
+        println!("Replay finished, final state does NOT match the recording");
+        std::process::exit(1);
+    }
+    let replay_speed = match flag_value(&args, "--speed") {
+        Some(speed) => match speed.parse::<f64>() {
+            Ok(speed) if speed > 0.0 && speed.is_finite() => speed,
+            _ => {
+                println!("Speed must be a number more than 0");
+                std::process::exit(1);
+            }
+        },
+        None => 1.0,
+    };
 
     //Set screen title to "Spice Harvesting"
     let screen_setup = ggez::conf::WindowSetup::default().title("Spice Harvesting");
-    //Set window size to 1000x600
-    let window_mode = ggez::conf::WindowMode::default().dimensions(1000.0, 600.0);
+    //Window starts at the size and mode it was last closed at (1000x600 the first time)
+    let settings = load_settings();
+    let fullscreen_type = if settings.fullscreen {
+        FullscreenType::Desktop
+    } else {
+        FullscreenType::Windowed
+    };
+    let window_mode = ggez::conf::WindowMode::default()
+        .dimensions(settings.window_width, settings.window_height)
+        .min_dimensions(480.0, 360.0)
+        .resizable(true)
+        .fullscreen_type(fullscreen_type);
 
     //Create ggez context
     let (mut ctx, event_loop) =
@@ -251,12 +1357,41 @@ pub fn main() {
             .window_mode(window_mode)
             .build()
             .unwrap();
+    //Put the window back where it was
+    if let (Some(x), Some(y)) = (settings.window_x, settings.window_y) {
+        let _ = ctx
+            .gfx
+            .set_window_position(winit::dpi::PhysicalPosition::new(x, y));
+    }
+    //A replay starts from its own recorded game and leaves the save alone
+    let (user, playback) = match replay.map(Playback::new) {
+        Some(Some((playback, user))) => (user, Some(playback)),
+        Some(None) => {
+            println!("Replay's starting state couldn't be read");
+            std::process::exit(1);
+        }
+        None => {
+            let mut user = load_or_new_game();
+            user.set_click_limits(settings.click_limits());
+            (user, None)
+        }
+    };
+    let click_limits = settings.click_limits();
     //Create game state
-    let mut state = GameState::new(&mut ctx).expect("Failed to create game state");
-    //If debug mode, give a bunch of spice for testing
-    if debug_mode {
-        println!("Debug mode enabled: giving 1,000,000 spice");
-        state.user.set_spice(1000000.0);
+    let mut state = GameState::new(&mut ctx, settings, user).expect("Failed to create game state");
+    if playback.is_some() {
+        state.playback = playback;
+        state.replay_speed = replay_speed;
+    } else {
+        //If debug mode, give a bunch of spice for testing
+        if debug_mode {
+            println!("Debug mode enabled: giving 1,000,000 spice");
+            state.user.set_spice(1000000.0);
+        }
+        //Recording starts after everything else so it begins from the game as it's played
+        if let Some(path) = record_path {
+            state.recorder = Some((Recorder::new(&state.user, click_limits), path));
+        }
     }
     //Run ggez event loop
     event::run(ctx, event_loop, state);
@@ -265,8 +1400,45 @@ pub fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use spice_harvesting::clickeritem::ClickerItem;
+    use spice_harvesting::item::Item;
     use std::path::Path;
 
+    #[test]
+    fn test_parse_page_command() {
+        assert_eq!(parse_page_command("shop 2"), Some((Tab::Shop, 2)));
+        assert_eq!(parse_page_command("inventory 1"), Some((Tab::Inventory, 1)));
+        assert_eq!(parse_page_command("shop"), None);
+        assert_eq!(parse_page_command("shop two"), None);
+        assert_eq!(parse_page_command("save 2"), None);
+    }
+
+    #[test]
+    fn test_parse_shop_input() {
+        assert_eq!(parse_shop_input("3"), Some((3, 1)));
+        assert_eq!(parse_shop_input("3 x10"), Some((3, 10)));
+        assert_eq!(parse_shop_input("3 25"), Some((3, 25)));
+        assert_eq!(parse_shop_input("3 x0"), None);
+        assert_eq!(parse_shop_input("exit"), None);
+        assert_eq!(parse_shop_input("3 x10 more"), None);
+    }
+
+    #[test]
+    fn test_parse_trade_command() {
+        assert_eq!(
+            parse_trade_command("sell spice 100"),
+            Some((OrderSide::Sell, 100.0, None))
+        );
+        assert_eq!(
+            parse_trade_command("buy spice 2.5 at 0.04"),
+            Some((OrderSide::Buy, 2.5, Some(0.04)))
+        );
+        assert_eq!(parse_trade_command("sell spice"), None);
+        assert_eq!(parse_trade_command("sell water 10"), None);
+        assert_eq!(parse_trade_command("buy spice 10 for 0.04"), None);
+        assert_eq!(parse_trade_command("buy spice 10 at"), None);
+    }
+
     #[test]
     fn test_game_save_load() {
         //Backup existing save file if it exists
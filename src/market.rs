@@ -0,0 +1,206 @@
+use crate::rng::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+//Solari per spice the price drifts back towards
+pub const MEAN_PRICE: f64 = 0.05;
+//CHOAM's markup when selling spice back to the player
+pub const SPREAD: f64 = 0.1;
+//Seconds between price changes
+pub const TICK: f64 = 10.0;
+//Fraction of the gap to the mean price closed every tick
+const REVERSION: f64 = 0.05;
+//Biggest random move in one tick, as a fraction of the mean price
+const VOLATILITY: f64 = 0.06;
+//Price never drops below this
+const MIN_PRICE: f64 = 0.005;
+//Ticks of price history kept for the market panel, one hour
+const HISTORY_LEN: usize = 360;
+
+//Which way a limit order trades spice
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum OrderSide {
+    //Sell spice once the price is at or above the limit
+    Sell,
+    //Buy spice once the price is at or below the limit
+    Buy,
+}
+
+//Spice to trade automatically once the market price reaches a limit
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LimitOrder {
+    pub side: OrderSide,
+    pub amount: f64,
+    pub price: f64,
+}
+
+impl LimitOrder {
+    //Whether the order should go through, given what selling and buying a spice actually go for
+    //Checked against the price the trade happens at, so a buy never pays more than its limit
+    pub fn triggered(&self, sell_price: f64, buy_price: f64) -> bool {
+        match self.side {
+            OrderSide::Sell => sell_price >= self.price,
+            OrderSide::Buy => buy_price <= self.price,
+        }
+    }
+
+    //Short description like "sell 100.00 spice at 0.0600"
+    pub fn describe(&self) -> String {
+        let side = match self.side {
+            OrderSide::Sell => "sell",
+            OrderSide::Buy => "buy",
+        };
+        format!("{} {:.2} spice at {:.4}", side, self.amount, self.price)
+    }
+}
+
+//CHOAM's spice price, a random walk that keeps getting pulled back to the mean
+#[derive(Serialize, Deserialize)]
+pub struct Market {
+    rng: Rng,
+    price: f64,
+    //Seconds until the price moves again
+    next_tick_in: f64,
+    //Oldest first, one entry per tick
+    history: VecDeque<f64>,
+    orders: Vec<LimitOrder>,
+}
+
+impl Default for Market {
+    fn default() -> Market {
+        Market::new(Rng::from_time().next_u64())
+    }
+}
+
+impl Market {
+    //Create a market from a seed, starting at the mean price
+    pub fn new(seed: u64) -> Market {
+        Market {
+            rng: Rng::new(seed),
+            price: MEAN_PRICE,
+            next_tick_in: TICK,
+            history: VecDeque::from([MEAN_PRICE]),
+            orders: vec![],
+        }
+    }
+
+    //Gets the current price in solari per spice
+    pub fn get_price(&self) -> f64 {
+        self.price
+    }
+
+    //Gets recent prices, oldest first
+    pub fn get_history(&self) -> &VecDeque<f64> {
+        &self.history
+    }
+
+    //Gets the limit orders waiting to go through
+    pub fn get_orders(&self) -> &[LimitOrder] {
+        &self.orders
+    }
+
+    //Adds a limit order
+    pub fn place_order(&mut self, order: LimitOrder) {
+        self.orders.push(order);
+    }
+
+    //Cancels every limit order, returns how many there were
+    pub fn cancel_orders(&mut self) -> usize {
+        std::mem::take(&mut self.orders).len()
+    }
+
+    //Takes the orders that go through at the prices the player sells and buys at
+    pub fn take_triggered(&mut self, sell_price: f64, buy_price: f64) -> Vec<LimitOrder> {
+        let (triggered, waiting) = std::mem::take(&mut self.orders)
+            .into_iter()
+            .partition(|order| order.triggered(sell_price, buy_price));
+        self.orders = waiting;
+        triggered
+    }
+
+    //Moves time forward, returns true if the price changed
+    //Moves at most once, so long steps should be split up with next_tick and orders checked after each
+    pub fn advance(&mut self, dt: f64) -> bool {
+        self.next_tick_in -= dt;
+        if self.next_tick_in > 0.0 {
+            return false;
+        }
+        self.next_tick_in += TICK;
+        let pull = REVERSION * (MEAN_PRICE - self.price);
+        let shock = self.rng.range(-VOLATILITY, VOLATILITY) * MEAN_PRICE;
+        self.price = (self.price + pull + shock).max(MIN_PRICE);
+        self.history.push_back(self.price);
+        if self.history.len() > HISTORY_LEN {
+            self.history.pop_front();
+        }
+        true
+    }
+
+    //Gets the seconds until the price next changes
+    pub fn next_tick(&self) -> f64 {
+        self.next_tick_in
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Runs the market for a number of ticks
+    fn run(market: &mut Market, ticks: usize) {
+        for _ in 0..ticks {
+            let step = market.next_tick();
+            assert!(market.advance(step));
+        }
+    }
+
+    #[test]
+    fn test_price_reverts_to_mean() {
+        let mut market = Market::new(17);
+        run(&mut market, 5000);
+        assert_eq!(market.get_history().len(), HISTORY_LEN);
+        let average = market.get_history().iter().sum::<f64>() / HISTORY_LEN as f64;
+        //Wanders around but stays near the mean over an hour
+        assert!((average - MEAN_PRICE).abs() < MEAN_PRICE * 0.5);
+        assert!(market.get_history().iter().all(|p| *p >= MIN_PRICE));
+    }
+
+    #[test]
+    fn test_same_seed_same_prices() {
+        let mut a = Market::new(3);
+        let mut b = Market::new(3);
+        run(&mut a, 100);
+        run(&mut b, 100);
+        assert_eq!(a.get_history(), b.get_history());
+        //Ticks only happen every TICK seconds
+        assert!(!a.advance(TICK / 2.0));
+    }
+
+    #[test]
+    fn test_limit_orders_trigger() {
+        let mut market = Market::new(1);
+        market.place_order(LimitOrder {
+            side: OrderSide::Sell,
+            amount: 10.0,
+            price: MEAN_PRICE,
+        });
+        market.place_order(LimitOrder {
+            side: OrderSide::Buy,
+            amount: 10.0,
+            price: MEAN_PRICE / 2.0,
+        });
+        //A buy at the market price still has to pay the markup on top, so it waits
+        market.place_order(LimitOrder {
+            side: OrderSide::Buy,
+            amount: 10.0,
+            price: MEAN_PRICE,
+        });
+        let triggered = market.take_triggered(MEAN_PRICE, MEAN_PRICE * (1.0 + SPREAD));
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].side, OrderSide::Sell);
+        assert_eq!(market.get_orders().len(), 2);
+        assert_eq!(market.take_triggered(MEAN_PRICE, MEAN_PRICE).len(), 1);
+        assert_eq!(market.cancel_orders(), 1);
+        assert!(market.get_orders().is_empty());
+    }
+}
@@ -0,0 +1,356 @@
+use crate::catalog;
+use crate::clickguard::ClickLimits;
+use crate::factions::Faction;
+use crate::market::OrderSide;
+use crate::userstate::{UserState, is_stall};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_reader, to_writer};
+use std::fs::File;
+
+//Everything the player can do that changes the game, replays are a list of these
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Input {
+    Click,
+    //Shop entry by its 0-based position, and how many
+    Buy {
+        entry: usize,
+        count: u32,
+    },
+    CollectSpiceBlow,
+    Evacuate,
+    SellSpice(f64),
+    BuySpice(f64),
+    PlaceOrder {
+        side: OrderSide,
+        amount: f64,
+        price: f64,
+    },
+    CancelOrders,
+    Pledge(Faction),
+    Prestige,
+    Research(String),
+    //Contract offer by its 0-based position
+    Accept(usize),
+    SetGoal(f64),
+    ClearGoal,
+    //A frame long enough that it was caught up instead of stepped through
+    CatchUp(f64),
+}
+
+//Does an input to the game, the same way the window does when the player does it
+pub fn apply(user: &mut UserState, input: &Input) {
+    match input {
+        Input::Click => {
+            user.update_spice_by_click();
+        }
+        Input::Buy { entry, count } => {
+            user.buy_shop_entries(*entry, *count);
+        }
+        Input::CollectSpiceBlow => {
+            user.collect_spice_blow();
+        }
+        Input::Evacuate => {
+            user.evacuate();
+        }
+        Input::SellSpice(amount) => {
+            user.sell_spice(*amount);
+        }
+        Input::BuySpice(amount) => {
+            user.buy_spice(*amount);
+        }
+        Input::PlaceOrder {
+            side,
+            amount,
+            price,
+        } => {
+            user.place_order(*side, *amount, *price);
+        }
+        Input::CancelOrders => {
+            user.cancel_orders();
+        }
+        Input::Pledge(faction) => {
+            user.pledge(*faction);
+        }
+        Input::Prestige => {
+            user.prestige(catalog::items(), catalog::clicker_items());
+        }
+        Input::Research(name) => {
+            user.start_research(name);
+        }
+        Input::Accept(index) => {
+            user.accept_contract(*index);
+        }
+        Input::SetGoal(amount) => {
+            user.set_goal(*amount);
+        }
+        Input::ClearGoal => {
+            user.clear_goal();
+        }
+        Input::CatchUp(dt) => user.catch_up(*dt),
+    }
+}
+
+//An input and the simulation step it happened on
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Entry {
+    pub tick: u64,
+    pub input: Input,
+}
+
+//A recorded session, enough to play it back exactly
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    //The game as it was when recording started, saved the same way as a save file
+    start: String,
+    //Click limits the session ran with, they decide which clicks counted
+    max_clicks_per_second: Option<u32>,
+    detect_regular_clicks: bool,
+    inputs: Vec<Entry>,
+    //Steps the session ran for and a hash of how it ended
+    end_tick: u64,
+    end_hash: u64,
+}
+
+impl Replay {
+    //Gets the click limits the session ran with
+    fn click_limits(&self) -> ClickLimits {
+        ClickLimits {
+            max_per_second: self.max_clicks_per_second,
+            detect_regular: self.detect_regular_clicks,
+        }
+    }
+}
+
+//Hashes everything about the game that's saved, so two games with the same hash ended up the same
+//When it was last saved isn't part of the game, so it's left out
+//FNV-1a, since the standard library's hasher isn't promised to stay the same between versions
+pub fn state_hash(user: &UserState) -> u64 {
+    let mut value = serde_json::to_value(user).expect("Game state always serializes");
+    if let Some(fields) = value.as_object_mut() {
+        fields.remove("time_last_updated");
+    }
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in value.to_string().bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+//Writes down inputs as the game is played
+pub struct Recorder {
+    replay: Replay,
+    tick: u64,
+}
+
+impl Recorder {
+    //Starts recording from the game as it is now
+    pub fn new(user: &UserState, limits: ClickLimits) -> Recorder {
+        Recorder {
+            replay: Replay {
+                start: serde_json::to_string(user).expect("Game state always serializes"),
+                max_clicks_per_second: limits.max_per_second,
+                detect_regular_clicks: limits.detect_regular,
+                inputs: vec![],
+                end_tick: 0,
+                end_hash: 0,
+            },
+            tick: 0,
+        }
+    }
+
+    //Writes down an input, call it right before doing the input
+    pub fn record(&mut self, input: Input) {
+        self.replay.inputs.push(Entry {
+            tick: self.tick,
+            input,
+        });
+    }
+
+    //Runs a frame on the game, writing down any time that had to be caught up instead of stepped
+    pub fn update(&mut self, user: &mut UserState, dt: f64) {
+        if is_stall(dt) {
+            self.record(Input::CatchUp(dt));
+        }
+        self.tick += user.update(dt) as u64;
+    }
+
+    //Stops recording, noting how the game ended up
+    pub fn finish(mut self, user: &UserState) -> Replay {
+        self.replay.end_tick = self.tick;
+        self.replay.end_hash = state_hash(user);
+        self.replay
+    }
+}
+
+//Plays a replay back onto a game built from its starting state
+pub struct Playback {
+    replay: Replay,
+    tick: u64,
+    //Next input to do
+    next: usize,
+}
+
+impl Playback {
+    //Sets up a replay, returning it and the game it starts from
+    pub fn new(replay: Replay) -> Option<(Playback, UserState)> {
+        let mut user: UserState = serde_json::from_str(&replay.start).ok()?;
+        //Item details aren't saved, same as loading a save file
+        user.sync_catalog(catalog::items(), catalog::clicker_items());
+        user.set_click_limits(replay.click_limits());
+        let playback = Playback {
+            replay,
+            tick: 0,
+            next: 0,
+        };
+        Some((playback, user))
+    }
+
+    //Whether every step of the replay has been played
+    pub fn is_finished(&self) -> bool {
+        self.tick >= self.replay.end_tick && self.next >= self.replay.inputs.len()
+    }
+
+    //Plays up to the given number of steps, doing each input on the step it happened
+    pub fn advance(&mut self, user: &mut UserState, steps: u64) {
+        let stop = (self.tick + steps).min(self.replay.end_tick);
+        loop {
+            while let Some(entry) = self.replay.inputs.get(self.next)
+                && entry.tick == self.tick
+            {
+                apply(user, &entry.input);
+                self.next += 1;
+            }
+            if self.tick >= stop {
+                break;
+            }
+            user.step();
+            self.tick += 1;
+        }
+    }
+
+    //Plays the rest of the replay
+    pub fn run_to_end(&mut self, user: &mut UserState) {
+        self.advance(user, self.replay.end_tick.saturating_sub(self.tick));
+    }
+
+    //Whether the game ended up the same way it did when recorded
+    pub fn matches(&self, user: &UserState) -> bool {
+        state_hash(user) == self.replay.end_hash
+    }
+}
+
+//Replays are saved with serde like the game
+pub fn save_replay(path: &str, replay: &Replay) -> bool {
+    File::create(path)
+        .ok()
+        .is_some_and(|file| to_writer(file, replay).is_ok())
+}
+
+//Loads a replay file, None if it's missing or can't be read
+pub fn load_replay(path: &str) -> Option<Replay> {
+    let file = File::open(path).ok()?;
+    from_reader(file).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::Item;
+
+    fn new_game() -> UserState {
+        let mut user = UserState::new(catalog::items(), catalog::clicker_items());
+        user.set_spice(50000.0);
+        user
+    }
+
+    //Plays a session, recording it along the way
+    fn play_session(user: &mut UserState) -> Replay {
+        let mut recorder = Recorder::new(user, ClickLimits::NONE);
+        let mut inputs = [
+            Input::Research(String::from("Desert Survey")),
+            Input::Buy {
+                entry: 1,
+                count: 10,
+            },
+            Input::Click,
+            Input::Buy { entry: 0, count: 5 },
+            Input::SellSpice(100.0),
+            Input::SetGoal(1e7),
+        ]
+        .into_iter();
+        for frame in 0..2000 {
+            if frame % 100 == 0
+                && let Some(input) = inputs.next()
+            {
+                recorder.record(input.clone());
+                apply(user, &input);
+            }
+            recorder.update(user, 1.0 / 60.0);
+        }
+        //The window froze for a while
+        recorder.update(user, 30.0);
+        recorder.record(Input::Click);
+        apply(user, &Input::Click);
+        recorder.update(user, 0.5);
+        recorder.finish(user)
+    }
+
+    #[test]
+    fn test_start_round_trips() {
+        //Every new game gets its own seeds, so a handful of them covers plenty of different floats
+        for _ in 0..20 {
+            let mut user = new_game();
+            apply(&mut user, &Input::Buy { entry: 0, count: 3 });
+            for _ in 0..500 {
+                user.step();
+            }
+            let replay = Recorder::new(&user, ClickLimits::NONE).finish(&user);
+            let (_, started) = Playback::new(replay).unwrap();
+            assert_eq!(state_hash(&started), state_hash(&user));
+            assert_eq!(started.get_spice(), user.get_spice());
+        }
+    }
+
+    #[test]
+    fn test_replay_matches() {
+        let mut user = new_game();
+        let replay = play_session(&mut user);
+        let json = serde_json::to_string(&replay).unwrap();
+        let replay: Replay = serde_json::from_str(&json).unwrap();
+        let (mut playback, mut replayed) = Playback::new(replay).unwrap();
+        playback.run_to_end(&mut replayed);
+        assert!(playback.is_finished());
+        assert!(playback.matches(&replayed));
+        assert_eq!(state_hash(&replayed), state_hash(&user));
+        assert!(replayed.amount_owned("Fremen") > 0);
+        assert_eq!(replayed.amount_owned("Fremen"), user.amount_owned("Fremen"));
+        assert_eq!(replayed.get_spice(), user.get_spice());
+    }
+
+    #[test]
+    fn test_replay_in_pieces_matches() {
+        let mut user = new_game();
+        let replay = play_session(&mut user);
+        let (mut playback, mut replayed) = Playback::new(replay).unwrap();
+        //Playing a few steps at a time like the window does lands in the same place
+        while !playback.is_finished() {
+            playback.advance(&mut replayed, 7);
+        }
+        assert!(playback.matches(&replayed));
+    }
+
+    #[test]
+    fn test_changed_game_doesnt_match() {
+        let mut user = new_game();
+        let replay = play_session(&mut user);
+        let (mut playback, mut replayed) = Playback::new(replay).unwrap();
+        playback.run_to_end(&mut replayed);
+        replayed.sync_catalog(
+            vec![Item::new("Fremen", 0, 2.0, 100)],
+            catalog::clicker_items(),
+        );
+        replayed.update_spice_by_click();
+        assert!(!playback.matches(&replayed));
+    }
+}
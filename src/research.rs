@@ -0,0 +1,165 @@
+use crate::clickeritem::ClickerItem;
+use crate::item::Item;
+use serde::{Deserialize, Serialize};
+
+//What finishing a technology gives the player
+pub enum Unlock {
+    //New building added to the shop
+    Item(Item),
+    //New clicker item added to the shop
+    Upgrade(ClickerItem),
+    //Command that can't be used until researched
+    Command(&'static str),
+}
+
+//One node of the research tree, the whole tree lives in the catalog
+pub struct Technology {
+    pub name: &'static str,
+    //Technologies that have to be finished first
+    pub prerequisites: &'static [&'static str],
+    pub cost: u32,
+    //Seconds it takes once started
+    pub duration: f64,
+    pub unlocks: Vec<Unlock>,
+}
+
+impl Technology {
+    //Names of what the technology unlocks, for the research panel
+    pub fn describe_unlocks(&self) -> String {
+        let names: Vec<String> = self
+            .unlocks
+            .iter()
+            .map(|unlock| match unlock {
+                Unlock::Item(item) => item.get_name().to_string(),
+                Unlock::Upgrade(clicker_item) => clicker_item.get_name().to_string(),
+                Unlock::Command(command) => format!("the {} command", command),
+            })
+            .collect();
+        names.join(", ")
+    }
+}
+
+//Where a technology stands for the player
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TechStatus {
+    Done,
+    //Seconds left until it's done
+    InProgress(f64),
+    //Every prerequisite is done
+    Available,
+    Locked,
+}
+
+//Technology being researched right now
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct InProgress {
+    name: String,
+    time_left: f64,
+}
+
+//Player's research progress, saved by technology name so the tree can grow between versions
+#[derive(Serialize, Deserialize, Default)]
+pub struct Research {
+    completed: Vec<String>,
+    current: Option<InProgress>,
+}
+
+impl Research {
+    //Gets where a technology stands
+    pub fn status(&self, tech: &Technology) -> TechStatus {
+        if self.is_done(tech.name) {
+            return TechStatus::Done;
+        }
+        if let Some(current) = self.current.as_ref()
+            && current.name == tech.name
+        {
+            return TechStatus::InProgress(current.time_left);
+        }
+        if tech.prerequisites.iter().all(|name| self.is_done(name)) {
+            TechStatus::Available
+        } else {
+            TechStatus::Locked
+        }
+    }
+
+    //Whether a technology has been finished
+    pub fn is_done(&self, name: &str) -> bool {
+        self.completed.iter().any(|done| done == name)
+    }
+
+    //Whether something is being researched
+    pub fn is_busy(&self) -> bool {
+        self.current.is_some()
+    }
+
+    //Gets the seconds left on the current research, None if nothing is running
+    pub fn time_left(&self) -> Option<f64> {
+        self.current.as_ref().map(|current| current.time_left)
+    }
+
+    //Starts researching a technology, the caller checks it's available and pays for it
+    pub fn start(&mut self, tech: &Technology) {
+        self.current = Some(InProgress {
+            name: tech.name.to_string(),
+            time_left: tech.duration,
+        });
+    }
+
+    //Moves research forward, returns the name of the technology if it just finished
+    pub fn advance(&mut self, dt: f64) -> Option<String> {
+        let current = self.current.as_mut()?;
+        current.time_left -= dt;
+        if current.time_left > 0.0 {
+            return None;
+        }
+        let name = self.current.take()?.name;
+        self.completed.push(name.clone());
+        Some(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tech(name: &'static str, prerequisites: &'static [&'static str]) -> Technology {
+        Technology {
+            name,
+            prerequisites,
+            cost: 10,
+            duration: 60.0,
+            unlocks: vec![Unlock::Command("test")],
+        }
+    }
+
+    #[test]
+    fn test_prerequisites() {
+        let mut research = Research::default();
+        let first = tech("First", &[]);
+        let second = tech("Second", &["First"]);
+        assert_eq!(research.status(&first), TechStatus::Available);
+        assert_eq!(research.status(&second), TechStatus::Locked);
+        research.start(&first);
+        assert!(research.is_busy());
+        assert_eq!(research.advance(30.0), None);
+        assert_eq!(research.status(&first), TechStatus::InProgress(30.0));
+        //Big offline steps still only finish what was started
+        assert_eq!(research.advance(1000.0), Some(String::from("First")));
+        assert!(!research.is_busy());
+        assert_eq!(research.status(&first), TechStatus::Done);
+        assert_eq!(research.status(&second), TechStatus::Available);
+        assert_eq!(research.advance(1000.0), None);
+    }
+
+    #[test]
+    fn test_describe_unlocks() {
+        let tech = Technology {
+            unlocks: vec![
+                Unlock::Item(Item::new("Factory", 0, 1.0, 10)),
+                Unlock::Command("limit orders"),
+            ],
+            ..tech("Industry", &[])
+        };
+        assert_eq!(tech.describe_unlocks(), "Factory, the limit orders command");
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Add;
+
+//Each Spice Refinery raises melange quality by this much
+pub const QUALITY_PER_REFINERY: f64 = 0.05;
+//Melange quality can't go past this, 2 is twice the base price
+pub const MAX_QUALITY: f64 = 2.0;
+
+//What something costs, in every resource it needs
+//Older saves stored a plain spice cost, which still loads
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(from = "SavedCost")]
+pub struct Cost {
+    pub spice: u32,
+    pub water: u32,
+    pub solari: u32,
+}
+
+//Either format a cost could be saved in
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedCost {
+    Spice(u32),
+    Bundle {
+        spice: u32,
+        #[serde(default)]
+        water: u32,
+        #[serde(default)]
+        solari: u32,
+    },
+}
+
+impl From<SavedCost> for Cost {
+    fn from(saved: SavedCost) -> Cost {
+        match saved {
+            SavedCost::Spice(spice) => Cost::spice(spice),
+            SavedCost::Bundle {
+                spice,
+                water,
+                solari,
+            } => Cost {
+                spice,
+                water,
+                solari,
+            },
+        }
+    }
+}
+
+impl Cost {
+    //Create a cost that only needs spice
+    pub fn spice(spice: u32) -> Cost {
+        Cost {
+            spice,
+            water: 0,
+            solari: 0,
+        }
+    }
+
+    //Multiplies every part of the cost, rounding up
+    pub fn scaled(&self, factor: f64) -> Cost {
+        let scale = |amount: u32| (amount as f64 * factor).ceil() as u32;
+        Cost {
+            spice: scale(self.spice),
+            water: scale(self.water),
+            solari: scale(self.solari),
+        }
+    }
+}
+
+//Adding costs adds each resource, used to price buying several at once
+impl Add for Cost {
+    type Output = Cost;
+
+    fn add(self, other: Cost) -> Cost {
+        Cost {
+            spice: self.spice.saturating_add(other.spice),
+            water: self.water.saturating_add(other.water),
+            solari: self.solari.saturating_add(other.solari),
+        }
+    }
+}
+
+//Spice-only costs print as just the number, like they always have
+impl fmt::Display for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.water == 0 && self.solari == 0 {
+            return write!(f, "{}", self.spice);
+        }
+        let mut parts = vec![];
+        for (amount, name) in [
+            (self.spice, "spice"),
+            (self.water, "water"),
+            (self.solari, "solari"),
+        ] {
+            if amount > 0 {
+                parts.push(format!("{} {}", amount, name));
+            }
+        }
+        write!(f, "{}", parts.join(" + "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_old_cost_loads() {
+        let cost: Cost = serde_json::from_str("1100").unwrap();
+        assert_eq!(cost, Cost::spice(1100));
+        let cost: Cost = serde_json::from_str(r#"{"spice":10,"water":5}"#).unwrap();
+        assert_eq!(cost.water, 5);
+        assert_eq!(cost.solari, 0);
+        //Saving and loading again keeps the whole bundle
+        let json = serde_json::to_string(&cost).unwrap();
+        assert_eq!(serde_json::from_str::<Cost>(&json).unwrap(), cost);
+    }
+
+    #[test]
+    fn test_scaled_and_display() {
+        let cost = Cost {
+            spice: 100,
+            water: 10,
+            solari: 0,
+        };
+        assert_eq!(cost.to_string(), "100 spice + 10 water");
+        assert_eq!(Cost::spice(2).to_string(), "2");
+        let scaled = cost.scaled(1.15);
+        assert_eq!(scaled.spice, 115);
+        assert_eq!(scaled.water, 12);
+        assert_eq!(scaled.solari, 0);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::clickguard::ClickLimits;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_reader, to_writer};
+use std::fs::File;
+use std::io::Read;
+
+//Smallest and largest UI scale allowed, anything outside gets hard to read or won't fit
+pub const MIN_UI_SCALE: f32 = 0.5;
+pub const MAX_UI_SCALE: f32 = 2.0;
+
+//Player preferences that aren't part of the game itself, kept in their own file
+//so deleting a save doesn't reset them
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    //Multiplier for all text and UI sizes
+    pub ui_scale: f32,
+    //Window size when not fullscreen
+    pub window_width: f32,
+    pub window_height: f32,
+    //Window position from the last run, None lets the OS decide
+    pub window_x: Option<i32>,
+    pub window_y: Option<i32>,
+    //Whether the game was fullscreen when it closed
+    pub fullscreen: bool,
+    //Whether clicks show floating numbers and particles
+    pub effects_enabled: bool,
+    //Most clicks a second that give spice, 0 for no cap
+    pub max_clicks_per_second: u32,
+    //Whether perfectly steady clicking marks the run as not leaderboard eligible
+    pub autoclick_detection: bool,
+    //Whether reaching a spice goal plays a chime
+    pub goal_sound: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            ui_scale: 1.0,
+            window_width: 1000.0,
+            window_height: 600.0,
+            window_x: None,
+            window_y: None,
+            fullscreen: false,
+            effects_enabled: true,
+            max_clicks_per_second: 20,
+            autoclick_detection: true,
+            goal_sound: true,
+        }
+    }
+}
+
+impl Settings {
+    //Sets the UI scale, keeping it in the allowed range
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        if scale.is_finite() {
+            self.ui_scale = scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+        }
+    }
+
+    //Gets the click analyzer limits these settings ask for
+    pub fn click_limits(&self) -> ClickLimits {
+        ClickLimits {
+            max_per_second: (self.max_clicks_per_second > 0).then_some(self.max_clicks_per_second),
+            detect_regular: self.autoclick_detection,
+        }
+    }
+}
+
+//Settings are saved the same way as the game, just in settings.json
+pub fn save_settings(settings: &Settings) {
+    let file = File::create("settings.json").expect("Unable to open or create settings file");
+    to_writer(file, settings).expect("Unable to write settings to file");
+}
+
+//Loads settings, falling back to defaults if there's no file or it can't be read
+pub fn load_settings() -> Settings {
+    File::open("settings.json")
+        .ok()
+        .and_then(read_settings)
+        .unwrap_or_default()
+}
+
+//Reads settings, keeping hand-edited values in the range the game can use
+fn read_settings(reader: impl Read) -> Option<Settings> {
+    let mut settings: Settings = from_reader(reader).ok()?;
+    let scale = if settings.ui_scale.is_finite() {
+        settings.ui_scale
+    } else {
+        1.0
+    };
+    settings.set_ui_scale(scale);
+    Some(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_ui_scale() {
+        let mut settings = Settings::default();
+        settings.set_ui_scale(1.5);
+        assert_eq!(settings.ui_scale, 1.5);
+        settings.set_ui_scale(10.0);
+        assert_eq!(settings.ui_scale, MAX_UI_SCALE);
+        settings.set_ui_scale(f32::NAN);
+        assert_eq!(settings.ui_scale, MAX_UI_SCALE);
+    }
+
+    #[test]
+    fn test_missing_fields_default() {
+        //Older settings files without every field should still load
+        let settings: Settings = serde_json::from_str(r#"{"ui_scale": 1.25}"#).unwrap();
+        assert_eq!(settings.ui_scale, 1.25);
+        assert_eq!(settings.window_width, 1000.0);
+        assert!(!settings.fullscreen);
+        assert!(settings.effects_enabled);
+        assert_eq!(settings.max_clicks_per_second, 20);
+        assert!(settings.goal_sound);
+    }
+
+    #[test]
+    fn test_loaded_scale_clamped() {
+        let settings = read_settings(r#"{"ui_scale": 0}"#.as_bytes()).unwrap();
+        assert_eq!(settings.ui_scale, MIN_UI_SCALE);
+        let settings = read_settings(r#"{"ui_scale": 100}"#.as_bytes()).unwrap();
+        assert_eq!(settings.ui_scale, MAX_UI_SCALE);
+        assert!(read_settings("not json".as_bytes()).is_none());
+    }
+
+    #[test]
+    fn test_click_limits() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.click_limits().max_per_second, Some(20));
+        settings.max_clicks_per_second = 0;
+        settings.autoclick_detection = false;
+        assert_eq!(settings.click_limits(), ClickLimits::NONE);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::catalog;
+use crate::clickguard::ClickLimits;
+use crate::clock::STEP;
+use crate::strategy::{Strategy, format_time};
+use crate::userstate::{PRESTIGE_SPICE, UserState};
+
+//Lifetime spice totals reported, every power of 10 from a thousand to a billion
+const SPICE_MILESTONES: [f64; 7] = [1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9];
+
+//Something worth reaching in a run
+enum Goal {
+    LifetimeSpice(f64),
+    FirstBuilding(String),
+    //Enough spice on hand to prestige
+    Prestige,
+}
+
+impl Goal {
+    fn is_reached(&self, user: &UserState) -> bool {
+        match self {
+            Goal::LifetimeSpice(amount) => user.get_lifetime_spice() >= *amount,
+            Goal::FirstBuilding(name) => user.amount_owned(name) > 0,
+            Goal::Prestige => user.get_spice() >= PRESTIGE_SPICE,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Goal::LifetimeSpice(amount) => format!("{} lifetime spice", amount),
+            Goal::FirstBuilding(name) => format!("First {}", name),
+            Goal::Prestige => String::from("Able to prestige"),
+        }
+    }
+}
+
+//A goal and how many seconds into the run it was reached
+pub struct Milestone {
+    goal: Goal,
+    pub time: Option<f64>,
+}
+
+impl Milestone {
+    fn new(goal: Goal) -> Milestone {
+        Milestone { goal, time: None }
+    }
+
+    //Gets what the milestone is for
+    pub fn describe(&self) -> String {
+        self.goal.describe()
+    }
+}
+
+//How a simulated run turned out
+pub struct Report {
+    pub strategy: &'static str,
+    pub hours: f64,
+    pub milestones: Vec<Milestone>,
+    pub purchases: u32,
+    pub final_spice: f64,
+    pub final_sps: f64,
+    //Buildings owned at the end
+    pub owned: Vec<(String, u32)>,
+}
+
+impl Report {
+    //Gets the report as text to print
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "Strategy: {}, {} hours simulated\n",
+            self.strategy, self.hours
+        );
+        for milestone in self.milestones.iter() {
+            let time = milestone
+                .time
+                .map_or(String::from("not reached"), format_time);
+            text.push_str(&format!("{}: {}\n", milestone.describe(), time));
+        }
+        text.push_str(&format!(
+            "Bought {} buildings, ending with {:.2} spice and {:.2} spice per second\n",
+            self.purchases, self.final_spice, self.final_sps
+        ));
+        for (name, amount) in self.owned.iter().filter(|(_, amount)| *amount > 0) {
+            text.push_str(&format!("  {}: {}\n", name, amount));
+        }
+        text
+    }
+}
+
+//Plays a new game from the catalog for the given hours with no window, buying buildings as the strategy picks
+//The bot clicks at a steady rate, evacuates every sandworm and collects every spice blow
+//Clicker upgrades, trading, research and contracts are left alone so buildings are all that's compared
+pub fn simulate(strategy: &mut Strategy, hours: f64, clicks_per_second: f64, seed: u64) -> Report {
+    let items = catalog::items();
+    let mut milestones: Vec<Milestone> = SPICE_MILESTONES
+        .iter()
+        .map(|&amount| Goal::LifetimeSpice(amount))
+        .chain(
+            items
+                .iter()
+                .map(|item| Goal::FirstBuilding(item.get_name().to_string())),
+        )
+        .chain([Goal::Prestige])
+        .map(Milestone::new)
+        .collect();
+    let mut user = UserState::new(items, catalog::clicker_items()).with_seed(seed);
+    user.set_quiet(true);
+    user.set_click_limits(ClickLimits::NONE);
+
+    let steps = (hours.max(0.0) * 3600.0 / STEP).round() as u64;
+    let mut clicks_due = 0.0;
+    let mut target = None;
+    let mut purchases = 0;
+    for tick in 0..steps {
+        clicks_due += clicks_per_second * STEP;
+        while clicks_due >= 1.0 {
+            user.update_spice_by_click();
+            clicks_due -= 1.0;
+        }
+        user.evacuate();
+        user.collect_spice_blow();
+
+        //Saves up for one building at a time, picking the next once it's bought
+        if target.is_none() {
+            let choices = user.purchase_choices();
+            target = strategy
+                .choose(&choices, user.get_spice(), user.get_sps())
+                .cloned();
+        }
+        if let Some(choice) = target.as_ref()
+            && choice.cost <= user.get_spice()
+        {
+            let owned = user.amount_owned(&choice.name);
+            user.buy_item(choice.index);
+            if user.amount_owned(&choice.name) > owned {
+                purchases += 1;
+                target = None;
+            }
+        }
+
+        user.step();
+        user.take_notices();
+        let time = (tick + 1) as f64 * STEP;
+        for milestone in milestones.iter_mut() {
+            if milestone.time.is_none() && milestone.goal.is_reached(&user) {
+                milestone.time = Some(time);
+            }
+        }
+    }
+
+    let owned = catalog::items()
+        .iter()
+        .map(|item| {
+            (
+                item.get_name().to_string(),
+                user.amount_owned(item.get_name()),
+            )
+        })
+        .collect();
+    Report {
+        strategy: strategy.name(),
+        hours,
+        milestones,
+        purchases,
+        final_spice: user.get_spice(),
+        final_sps: user.get_sps(),
+        owned,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reached(report: &Report, name: &str) -> Option<f64> {
+        report
+            .milestones
+            .iter()
+            .find(|milestone| milestone.describe() == name)
+            .and_then(|milestone| milestone.time)
+    }
+
+    #[test]
+    fn test_simulated_run() {
+        let report = simulate(&mut Strategy::Payback, 0.5, 5.0, 42);
+        assert!(report.purchases > 0);
+        assert!(report.final_sps > 0.0);
+        let thousand = reached(&report, "1000 lifetime spice").expect("Should reach 1000 spice");
+        let fremen = reached(&report, "First Fremen").expect("Should buy a Fremen");
+        //Clicking 5 times a second is enough for a Fremen well before 1000 spice
+        assert!(fremen < thousand);
+        assert!(reached(&report, "1000000000 lifetime spice").is_none());
+        assert!(
+            report
+                .summary()
+                .contains("Strategy: payback, 0.5 hours simulated")
+        );
+    }
+
+    #[test]
+    fn test_same_seed_same_run() {
+        let first = simulate(&mut Strategy::parse("random", 3).unwrap(), 0.2, 5.0, 9);
+        let second = simulate(&mut Strategy::parse("random", 3).unwrap(), 0.2, 5.0, 9);
+        assert_eq!(first.summary(), second.summary());
+    }
+}
@@ -0,0 +1,70 @@
+use std::f32::consts::TAU;
+
+//Samples per second of the generated sounds
+const SAMPLE_RATE: u32 = 22050;
+//Notes of the goal chime in Hz, a rising major arpeggio
+const CHIME_NOTES: [f32; 3] = [523.25, 659.25, 783.99];
+//Seconds each note of the chime lasts
+const NOTE_LENGTH: f32 = 0.15;
+//How loud the chime is, out of 1
+const VOLUME: f32 = 0.3;
+
+//Builds the goal chime as a WAV file in memory, so the game doesn't need any sound files next to it
+pub fn goal_chime() -> Vec<u8> {
+    let note_samples = (SAMPLE_RATE as f32 * NOTE_LENGTH) as usize;
+    let samples: Vec<i16> = CHIME_NOTES
+        .iter()
+        .flat_map(|&frequency| {
+            (0..note_samples).map(move |i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                //Fades out over the note so they don't click into each other
+                let fade = 1.0 - i as f32 / note_samples as f32;
+                ((TAU * frequency * t).sin() * fade * VOLUME * i16::MAX as f32) as i16
+            })
+        })
+        .collect();
+    wav(&samples)
+}
+
+//Wraps 16 bit mono samples in a WAV header
+fn wav(samples: &[i16]) -> Vec<u8> {
+    let data_size = samples.len() as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_size as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    //Format chunk: 16 bytes long, plain PCM, 1 channel
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    //Bytes per second, then bytes per sample and bits per sample
+    bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_goal_chime() {
+        let chime = goal_chime();
+        assert_eq!(&chime[0..4], b"RIFF");
+        assert_eq!(&chime[8..16], b"WAVEfmt ");
+        //Header says how much follows it
+        let riff_size = u32::from_le_bytes(chime[4..8].try_into().unwrap());
+        assert_eq!(riff_size as usize, chime.len() - 8);
+        let data_size = u32::from_le_bytes(chime[40..44].try_into().unwrap());
+        assert_eq!(data_size as usize, chime.len() - 44);
+        //Three notes of 0.15 seconds
+        assert_eq!(data_size, 3 * 3307 * 2);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::rng::Rng;
+use serde::{Deserialize, Serialize};
+
+//Shortest and longest wait between spice blows, in seconds
+const MIN_BLOW_GAP: f64 = 120.0;
+const MAX_BLOW_GAP: f64 = 360.0;
+//Seconds a spice blow stays on screen before sinking back into the sand
+pub const BLOW_LIFETIME: f64 = 13.0;
+//Minutes of sps given by the harvest reward
+pub const HARVEST_MINUTES: f64 = 10.0;
+//Frenzy multiplies sps for a while
+pub const FRENZY_MULTIPLIER: f64 = 7.0;
+pub const FRENZY_DURATION: f64 = 77.0;
+//Click frenzy multiplies spice per click for a shorter while
+pub const CLICK_FRENZY_MULTIPLIER: f64 = 777.0;
+pub const CLICK_FRENZY_DURATION: f64 = 13.0;
+
+//What collecting a spice blow gives
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlowReward {
+    //Instant spice worth several minutes of sps
+    Harvest,
+    Frenzy,
+    ClickFrenzy,
+}
+
+//A spice blow on screen, position is a fraction of the play area so it works at any window size
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ActiveBlow {
+    pub x: f32,
+    pub y: f32,
+    //Seconds left to click it
+    pub time_left: f64,
+}
+
+//Schedules spice blows and picks their rewards
+#[derive(Serialize, Deserialize)]
+pub struct SpiceBlows {
+    rng: Rng,
+    //Seconds until the next blow appears, only counts down while none is showing
+    next_blow_in: f64,
+    active: Option<ActiveBlow>,
+}
+
+impl Default for SpiceBlows {
+    fn default() -> SpiceBlows {
+        SpiceBlows::new(Rng::from_time().next_u64())
+    }
+}
+
+impl SpiceBlows {
+    //Create a spice blow schedule from a seed
+    pub fn new(seed: u64) -> SpiceBlows {
+        let mut rng = Rng::new(seed);
+        let next_blow_in = rng.range(MIN_BLOW_GAP, MAX_BLOW_GAP);
+        SpiceBlows {
+            rng,
+            next_blow_in,
+            active: None,
+        }
+    }
+
+    //Gets the spice blow on screen, if any
+    pub fn get_active(&self) -> Option<&ActiveBlow> {
+        self.active.as_ref()
+    }
+
+    //Counts down timers, returns true if a new blow just appeared
+    pub fn advance(&mut self, dt: f64) -> bool {
+        if let Some(active) = self.active.as_mut() {
+            active.time_left -= dt;
+            if active.time_left <= 0.0 {
+                self.active = None;
+                self.schedule_next();
+            }
+            return false;
+        }
+        self.next_blow_in -= dt;
+        if self.next_blow_in > 0.0 {
+            return false;
+        }
+        self.active = Some(ActiveBlow {
+            x: self.rng.next_f64() as f32,
+            y: self.rng.next_f64() as f32,
+            time_left: BLOW_LIFETIME,
+        });
+        true
+    }
+
+    //Collects the spice blow on screen and picks its reward
+    pub fn collect(&mut self) -> Option<BlowReward> {
+        self.active.take()?;
+        self.schedule_next();
+        //Harvest is most common, click frenzy is rare
+        let roll = self.rng.next_f64();
+        let reward = if roll < 0.5 {
+            BlowReward::Harvest
+        } else if roll < 0.9 {
+            BlowReward::Frenzy
+        } else {
+            BlowReward::ClickFrenzy
+        };
+        Some(reward)
+    }
+
+    fn schedule_next(&mut self) {
+        self.next_blow_in = self.rng.range(MIN_BLOW_GAP, MAX_BLOW_GAP);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Steps one second at a time until a blow appears
+    fn wait_for_blow(blows: &mut SpiceBlows) -> bool {
+        (0..MAX_BLOW_GAP as u32 + 1).any(|_| blows.advance(1.0))
+    }
+
+    #[test]
+    fn test_blow_appears_and_sinks() {
+        let mut blows = SpiceBlows::new(11);
+        assert!(wait_for_blow(&mut blows));
+        let active = blows.get_active().unwrap();
+        assert!((0.0..1.0).contains(&active.x));
+        assert!((0.0..1.0).contains(&active.y));
+        //Not clicked in time
+        blows.advance(BLOW_LIFETIME);
+        assert!(blows.get_active().is_none());
+        assert_eq!(blows.collect(), None);
+    }
+
+    #[test]
+    fn test_collect_rewards_repeatable() {
+        let mut a = SpiceBlows::new(4);
+        let mut b = SpiceBlows::new(4);
+        for _ in 0..20 {
+            assert!(wait_for_blow(&mut a));
+            assert!(wait_for_blow(&mut b));
+            let reward = a.collect();
+            assert!(reward.is_some());
+            assert_eq!(reward, b.collect());
+        }
+    }
+}
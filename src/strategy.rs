@@ -0,0 +1,166 @@
+use crate::rng::Rng;
+
+//A building that could be bought next and what it would do
+#[derive(Clone, PartialEq, Debug)]
+pub struct Choice {
+    //Position of the item in the shop
+    pub index: usize,
+    pub name: String,
+    //Spice price of the next one
+    pub cost: f64,
+    //How much sps would go up by buying it
+    pub sps_gain: f64,
+}
+
+impl Choice {
+    //Spice paid for each spice per second it adds, lower is better value
+    pub fn cost_per_sps(&self) -> Option<f64> {
+        (self.sps_gain > 0.0).then(|| self.cost / self.sps_gain)
+    }
+
+    //Seconds until buying it has paid for itself, given spice on hand and current sps
+    //That's the wait to afford it plus the time its extra sps takes to earn its price back
+    //None if it adds nothing, or it can't be afforded and there's no sps to save up with
+    pub fn payback_time(&self, spice: f64, sps: f64) -> Option<f64> {
+        let earn_back = self.cost_per_sps()?;
+        let wait = if self.cost <= spice {
+            0.0
+        } else if sps > 0.0 {
+            (self.cost - spice) / sps
+        } else {
+            return None;
+        };
+        Some(wait + earn_back)
+    }
+}
+
+//Picks the choice that pays for itself soonest
+//With no sps to save up with, the best value for its price is picked instead
+pub fn best_payback(choices: &[Choice], spice: f64, sps: f64) -> Option<&Choice> {
+    let score = |choice: &Choice| {
+        choice
+            .payback_time(spice, sps)
+            .or(choice.cost_per_sps())
+            .unwrap_or(f64::INFINITY)
+    };
+    choices
+        .iter()
+        .filter(|choice| choice.sps_gain > 0.0)
+        .min_by(|a, b| score(a).total_cmp(&score(b)))
+}
+
+//Formats seconds as hours, minutes and seconds, like 1h 02m 05s
+pub fn format_time(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{}h {:02}m {:02}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+//How a bot decides what to buy next
+pub enum Strategy {
+    //Always the cheapest building
+    Cheapest,
+    //Whatever pays for itself soonest
+    Payback,
+    //Any building at all
+    Random(Rng),
+}
+
+impl Strategy {
+    //Gets a strategy by name, the seed is only used by random
+    pub fn parse(name: &str, seed: u64) -> Option<Strategy> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "cheapest" => Some(Strategy::Cheapest),
+            "payback" => Some(Strategy::Payback),
+            "random" => Some(Strategy::Random(Rng::new(seed))),
+            _ => None,
+        }
+    }
+
+    //Gets the name of the strategy
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::Cheapest => "cheapest",
+            Strategy::Payback => "payback",
+            Strategy::Random(_) => "random",
+        }
+    }
+
+    //Picks the building to buy next, None if there's nothing to buy
+    pub fn choose<'a>(
+        &mut self,
+        choices: &'a [Choice],
+        spice: f64,
+        sps: f64,
+    ) -> Option<&'a Choice> {
+        match self {
+            Strategy::Cheapest => choices.iter().min_by(|a, b| a.cost.total_cmp(&b.cost)),
+            Strategy::Payback => best_payback(choices, spice, sps),
+            Strategy::Random(rng) => {
+                let pick = (rng.next_f64() * choices.len() as f64) as usize;
+                choices.get(pick)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn choice(index: usize, cost: f64, sps_gain: f64) -> Choice {
+        Choice {
+            index,
+            name: format!("Item {}", index),
+            cost,
+            sps_gain,
+        }
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(5.4), "5s");
+        assert_eq!(format_time(125.0), "2m 05s");
+        assert_eq!(format_time(3725.0), "1h 02m 05s");
+    }
+
+    #[test]
+    fn test_payback_time() {
+        let fremen = choice(0, 100.0, 1.0);
+        assert_eq!(fremen.cost_per_sps(), Some(100.0));
+        //Already affordable, so it's just earning the price back
+        assert_eq!(fremen.payback_time(500.0, 10.0), Some(100.0));
+        //60 more spice at 10 a second is 6 seconds of saving first
+        assert_eq!(fremen.payback_time(40.0, 10.0), Some(106.0));
+        assert_eq!(fremen.payback_time(40.0, 0.0), None);
+        assert_eq!(choice(1, 100.0, 0.0).payback_time(500.0, 10.0), None);
+    }
+
+    #[test]
+    fn test_strategies() {
+        let choices = vec![
+            choice(0, 15.0, 0.1),
+            choice(1, 100.0, 1.0),
+            choice(2, 1100.0, 12.0),
+        ];
+        let pick = |strategy: &mut Strategy, sps: f64| {
+            strategy
+                .choose(&choices, 0.0, sps)
+                .map(|choice| choice.index)
+        };
+        assert_eq!(pick(&mut Strategy::Cheapest, 10.0), Some(0));
+        //Ornithopters are the best value, but Fremen pay back sooner counting the wait to afford them
+        assert_eq!(pick(&mut Strategy::Payback, 0.0), Some(2));
+        assert_eq!(pick(&mut Strategy::Payback, 10.0), Some(1));
+        let mut random = Strategy::parse("random", 7).unwrap();
+        assert!(pick(&mut random, 1.0).is_some());
+        assert_eq!(random.choose(&[], 0.0, 1.0), None);
+        assert!(Strategy::parse("nonsense", 7).is_none());
+    }
+}
@@ -0,0 +1,47 @@
+//One building making another more productive, the rules live in the catalog
+pub struct Synergy {
+    //Building that gives the bonus
+    pub source: &'static str,
+    //Building whose output goes up
+    pub target: &'static str,
+    //Fraction added to the target's output for each source owned, 0.01 is 1%
+    pub bonus_per: f64,
+}
+
+//Gets the total bonus on a building from every rule, given a way to count owned buildings
+//Bonuses from different sources add up, so two 1% rules with 10 sources each give 20%
+pub fn synergy_bonus(synergies: &[Synergy], target: &str, owned: impl Fn(&str) -> u32) -> f64 {
+    synergies
+        .iter()
+        .filter(|synergy| synergy.target == target)
+        .map(|synergy| synergy.bonus_per * owned(synergy.source) as f64)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synergy_bonus() {
+        let synergies = [
+            Synergy {
+                source: "Sietch",
+                target: "Fremen",
+                bonus_per: 0.01,
+            },
+            Synergy {
+                source: "Ornithopter",
+                target: "Fremen",
+                bonus_per: 0.005,
+            },
+        ];
+        let owned = |name: &str| match name {
+            "Sietch" => 10,
+            "Ornithopter" => 4,
+            _ => 0,
+        };
+        assert!((synergy_bonus(&synergies, "Fremen", owned) - 0.12).abs() < 1e-12);
+        assert_eq!(synergy_bonus(&synergies, "Sietch", owned), 0.0);
+    }
+}
@@ -0,0 +1,531 @@
+use crate::history::GraphRange;
+use ggez::graphics::Rect;
+
+//Space kept between the window edge and anything drawn
+pub const OFFSET: f32 = 32.0;
+//Text size for the spice info at the top
+pub const TOP_TEXT_SIZE: f32 = 48.0;
+//Text size for everything else
+pub const NORMAL_TEXT_SIZE: f32 = 32.0;
+//Text size for the tab labels and tooltips
+pub const SMALL_TEXT_SIZE: f32 = 24.0;
+
+//The panels that can be shown under the spice info
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tab {
+    Shop,
+    Inventory,
+    Stats,
+    Graph,
+    Market,
+    Research,
+    Contracts,
+}
+
+impl Tab {
+    //Tabs in the order they're drawn left to right
+    pub const ALL: [Tab; 7] = [
+        Tab::Shop,
+        Tab::Inventory,
+        Tab::Stats,
+        Tab::Graph,
+        Tab::Market,
+        Tab::Research,
+        Tab::Contracts,
+    ];
+
+    //Text drawn on the tab button
+    pub fn label(&self) -> &'static str {
+        match self {
+            Tab::Shop => "Shop",
+            Tab::Inventory => "Inventory",
+            Tab::Stats => "Stats",
+            Tab::Graph => "Graph",
+            Tab::Market => "Market",
+            Tab::Research => "Research",
+            Tab::Contracts => "Contracts",
+        }
+    }
+}
+
+//What a mouse click on part of the screen should do
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UiAction {
+    Harvest,
+    SelectTab(Tab),
+    //0-based position in the shop list
+    BuyShopEntry(usize),
+    SelectRange(GraphRange),
+    //Respond to the current random event
+    Evacuate,
+    CollectSpiceBlow,
+    //Hovering a buff icon, clicking does nothing
+    BuffIcon(usize),
+}
+
+//What's currently on screen, needed to know which regions can be clicked
+pub struct View {
+    pub tab: Tab,
+    pub num_shop_entries: usize,
+    //How many body lines are hidden above the top of the panel
+    pub scroll: usize,
+    //Whether a random event banner is showing
+    pub event_active: bool,
+    //Position of the spice blow on screen as fractions of the play area, if there is one
+    pub spice_blow: Option<(f32, f32)>,
+    //Number of buffs and debuffs running
+    pub num_buffs: usize,
+}
+
+//Where everything goes on screen, worked out from the window size every frame
+pub struct Layout {
+    //Multiplier applied to every size below
+    pub scale: f32,
+    //Text sizes after scaling
+    pub top_text: f32,
+    pub normal_text: f32,
+    pub small_text: f32,
+    //Big button that harvests spice when clicked
+    pub harvest: Rect,
+    //Top left of the line with water, solari and melange quality under the spice info
+    pub resources: (f32, f32),
+    //Progress bar towards the spice goal, on the same line under the Harvest button
+    pub goal_bar: Rect,
+    //One button per tab, same order as Tab::ALL
+    pub tabs: Vec<Rect>,
+    //Area the shop/inventory/stats text is drawn in
+    pub panel: Rect,
+    //Top left of the command prompt
+    pub prompt: (f32, f32),
+    //Height of one line of panel text
+    pub row_height: f32,
+}
+
+impl Layout {
+    //Build the layout for a window of the given size
+    //Line ratio is how tall a line of text is compared to its text size, measured from the font
+    pub fn new(width: f32, height: f32, scale: f32, line_ratio: f32) -> Layout {
+        let offset = OFFSET * scale;
+        let gap = 8.0 * scale;
+        let top_text = TOP_TEXT_SIZE * scale;
+        let normal_text = NORMAL_TEXT_SIZE * scale;
+        let small_text = SMALL_TEXT_SIZE * scale;
+
+        //Harvest button shrinks on narrow windows so it doesn't cover the spice info
+        let harvest_w = (240.0 * scale).min(width / 3.0);
+        let harvest_h = top_text * 3.0;
+        let harvest = Rect::new(width - offset - harvest_w, offset, harvest_w, harvest_h);
+
+        //Other resources get a smaller line right under the spice info
+        let resources_y = offset + harvest_h + gap;
+
+        //Tabs go a little under the resources and split the width if there isn't room
+        let tab_y = resources_y + small_text + gap;
+        let tab_count = Tab::ALL.len() as f32;
+        let tab_w = (125.0 * scale)
+            .min((width - offset * 2.0 - gap * (tab_count - 1.0)) / tab_count)
+            .max(0.0);
+        let tab_h = small_text + 16.0 * scale;
+        let tabs = (0..Tab::ALL.len())
+            .map(|i| Rect::new(offset + i as f32 * (tab_w + gap), tab_y, tab_w, tab_h))
+            .collect();
+
+        //Panel fills the space between the tabs and the prompt
+        let prompt_y = height - offset - normal_text;
+        let panel_y = tab_y + tab_h + gap;
+        let panel = Rect::new(
+            offset,
+            panel_y,
+            (width - offset * 2.0).max(0.0),
+            (prompt_y - gap - panel_y).max(0.0),
+        );
+
+        Layout {
+            scale,
+            top_text,
+            normal_text,
+            small_text,
+            harvest,
+            resources: (offset, resources_y),
+            goal_bar: Rect::new(harvest.x, resources_y, harvest.w, small_text),
+            tabs,
+            panel,
+            prompt: (offset, prompt_y),
+            row_height: (normal_text * line_ratio).max(1.0),
+        }
+    }
+
+    //Gets the area of one line of text in the panel, row 0 is the header
+    pub fn panel_row(&self, row: usize) -> Rect {
+        Rect::new(
+            self.panel.x,
+            self.panel.y + row as f32 * self.row_height,
+            self.panel.w,
+            self.row_height,
+        )
+    }
+
+    //Gets the range buttons on the right of the graph panel's header, same order as GraphRange::ALL
+    pub fn graph_buttons(&self) -> Vec<Rect> {
+        let header = self.panel_row(0);
+        let button_w = 100.0 * self.scale;
+        let gap = 8.0 * self.scale;
+        let count = GraphRange::ALL.len();
+        (0..count)
+            .map(|i| {
+                let x = header.right() - (count - i) as f32 * (button_w + gap) + gap;
+                Rect::new(x, header.y, button_w, header.h)
+            })
+            .collect()
+    }
+
+    //Gets the area the graph is drawn in, under the header and legend rows
+    pub fn graph_area(&self) -> Rect {
+        let top = self.panel_row(2).y;
+        Rect::new(
+            self.panel.x,
+            top,
+            self.panel.w,
+            (self.panel.bottom() - top).max(0.0),
+        )
+    }
+
+    //Gets the warning banner shown over the top of the panel during a random event
+    pub fn event_banner(&self) -> Rect {
+        Rect::new(
+            self.panel.x,
+            self.panel.y,
+            self.panel.w,
+            (self.row_height * 2.0).min(self.panel.h),
+        )
+    }
+
+    //Gets the Evacuate button on the right of the event banner
+    pub fn evacuate_button(&self) -> Rect {
+        let banner = self.event_banner();
+        let margin = 6.0 * self.scale;
+        let w = (200.0 * self.scale).min(banner.w);
+        Rect::new(
+            banner.right() - w - margin,
+            banner.y + margin,
+            w,
+            (banner.h - margin * 2.0).max(0.0),
+        )
+    }
+
+    //Gets where a spice blow is drawn, x and y are fractions of the space it can appear in
+    pub fn spice_blow(&self, x: f32, y: f32) -> Rect {
+        let size = 80.0 * self.scale;
+        //Keep it between the edges and above the prompt so it's always clickable
+        let left = self.panel.x;
+        let top = self.harvest.y;
+        let w = (self.panel.w - size).max(0.0);
+        let h = (self.panel.bottom() - size - top).max(0.0);
+        Rect::new(
+            left + x.clamp(0.0, 1.0) * w,
+            top + y.clamp(0.0, 1.0) * h,
+            size,
+            size,
+        )
+    }
+
+    //Gets the icons for running buffs, lined up from the right end of the resources line
+    //They stop at the middle of the window so they stay clear of the resources text
+    //Only as many as fit are returned, so there may be fewer than count
+    pub fn buff_icons(&self, count: usize) -> Vec<Rect> {
+        let gap = 8.0 * self.scale;
+        let size = self.small_text + gap;
+        let y = self.resources.1 - gap / 2.0;
+        let leftmost = self.panel.center().x;
+        let mut icons = vec![];
+        let mut x = self.panel.right() - size;
+        while icons.len() < count && x >= leftmost {
+            icons.push(Rect::new(x, y, size, size));
+            x -= size + gap;
+        }
+        icons
+    }
+
+    //Gets how many lines fit in the panel under the header
+    pub fn visible_rows(&self) -> usize {
+        ((self.panel.h / self.row_height).floor() as usize).saturating_sub(1)
+    }
+
+    //Finds what's under the given point, shop rows only count when the shop tab is open
+    pub fn hit_test(&self, x: f32, y: f32, view: &View) -> Option<UiAction> {
+        let point = ggez::glam::Vec2::new(x, y);
+        //Spice blow is drawn on top of everything else
+        if let Some((blow_x, blow_y)) = view.spice_blow
+            && self.spice_blow(blow_x, blow_y).contains(point)
+        {
+            return Some(UiAction::CollectSpiceBlow);
+        }
+        //Event banner covers the panel so it's checked next
+        if view.event_active {
+            if self.evacuate_button().contains(point) {
+                return Some(UiAction::Evacuate);
+            }
+            if self.event_banner().contains(point) {
+                return None;
+            }
+        }
+        if self.harvest.contains(point) {
+            return Some(UiAction::Harvest);
+        }
+        for (i, rect) in self.tabs.iter().enumerate() {
+            if rect.contains(point) {
+                return Some(UiAction::SelectTab(Tab::ALL[i]));
+            }
+        }
+        for (i, rect) in self.buff_icons(view.num_buffs).iter().enumerate() {
+            if rect.contains(point) {
+                return Some(UiAction::BuffIcon(i));
+            }
+        }
+        if view.tab == Tab::Graph {
+            for (i, rect) in self.graph_buttons().iter().enumerate() {
+                if rect.contains(point) {
+                    return Some(UiAction::SelectRange(GraphRange::ALL[i]));
+                }
+            }
+        }
+        if view.tab == Tab::Shop {
+            //Entries start on row 1, under the header
+            for row in 0..self.visible_rows() {
+                let entry = view.scroll + row;
+                if entry >= view.num_shop_entries {
+                    break;
+                }
+                if self.panel_row(row + 1).contains(point) {
+                    return Some(UiAction::BuyShopEntry(entry));
+                }
+            }
+        }
+        None
+    }
+}
+
+//Splits panel text into its header line and the lines under it
+pub fn split_panel_text(text: &str) -> (&str, Vec<&str>) {
+    let mut lines = text.lines();
+    let header = lines.next().unwrap_or("");
+    (header, lines.collect())
+}
+
+//Keeps scroll from going past the last page of lines
+pub fn clamp_scroll(scroll: usize, total_lines: usize, visible_rows: usize) -> usize {
+    scroll.min(total_lines.saturating_sub(visible_rows))
+}
+
+//Gets the lines on one page (starting at page 1) and how many pages there are
+//Pages past the end give the last page
+pub fn paginate<'a>(lines: &[&'a str], page: usize, per_page: usize) -> (Vec<&'a str>, usize) {
+    let per_page = per_page.max(1);
+    let total_pages = lines.len().div_ceil(per_page).max(1);
+    let page = page.clamp(1, total_pages);
+    let start = (page - 1) * per_page;
+    let end = (start + per_page).min(lines.len());
+    (lines[start..end].to_vec(), total_pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(tab: Tab, num_shop_entries: usize, scroll: usize) -> View {
+        View {
+            tab,
+            num_shop_entries,
+            scroll,
+            event_active: false,
+            spice_blow: None,
+            num_buffs: 0,
+        }
+    }
+
+    #[test]
+    fn test_hit_harvest_and_tabs() {
+        let layout = Layout::new(1000.0, 600.0, 1.0, 1.0);
+        let harvest = layout.harvest;
+        assert_eq!(
+            layout.hit_test(
+                harvest.x + 1.0,
+                harvest.y + 1.0,
+                &view(Tab::Inventory, 0, 0)
+            ),
+            Some(UiAction::Harvest)
+        );
+        let stats_tab = layout.tabs[2];
+        assert_eq!(
+            layout.hit_test(
+                stats_tab.x + 1.0,
+                stats_tab.y + 1.0,
+                &view(Tab::Inventory, 0, 0)
+            ),
+            Some(UiAction::SelectTab(Tab::Stats))
+        );
+        //Clicking on empty space does nothing
+        assert_eq!(layout.hit_test(1.0, 1.0, &view(Tab::Inventory, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_hit_shop_rows() {
+        let layout = Layout::new(1000.0, 600.0, 1.0, 1.0);
+        let second_entry = layout.panel_row(2);
+        let (x, y) = (second_entry.x + 5.0, second_entry.y + 5.0);
+        assert_eq!(
+            layout.hit_test(x, y, &view(Tab::Shop, 3, 0)),
+            Some(UiAction::BuyShopEntry(1))
+        );
+        //Rows can't be bought from other tabs or past the end of the list
+        assert_eq!(layout.hit_test(x, y, &view(Tab::Inventory, 3, 0)), None);
+        assert_eq!(layout.hit_test(x, y, &view(Tab::Shop, 1, 0)), None);
+        //Header row isn't an entry
+        let header = layout.panel_row(0);
+        assert_eq!(
+            layout.hit_test(header.x + 5.0, header.y + 5.0, &view(Tab::Shop, 3, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_hit_graph_buttons() {
+        let layout = Layout::new(1000.0, 600.0, 1.0, 1.0);
+        let day = layout.graph_buttons()[2];
+        //Day button is the last one, against the right edge of the panel
+        assert_eq!(day.right(), layout.panel.right());
+        assert_eq!(
+            layout.hit_test(day.x + 1.0, day.y + 1.0, &view(Tab::Graph, 0, 0)),
+            Some(UiAction::SelectRange(GraphRange::Day))
+        );
+        assert_eq!(
+            layout.hit_test(day.x + 1.0, day.y + 1.0, &view(Tab::Shop, 0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_hit_event_banner() {
+        let layout = Layout::new(1000.0, 600.0, 1.0, 1.0);
+        let mut event_view = view(Tab::Shop, 3, 0);
+        event_view.event_active = true;
+        let evacuate = layout.evacuate_button();
+        assert_eq!(
+            layout.hit_test(evacuate.x + 1.0, evacuate.y + 1.0, &event_view),
+            Some(UiAction::Evacuate)
+        );
+        //Banner covers the first shop row, so it can't be bought by accident
+        let row = layout.panel_row(1);
+        assert_eq!(layout.hit_test(row.x + 1.0, row.y + 1.0, &event_view), None);
+        //No banner, no button
+        assert_eq!(
+            layout.hit_test(evacuate.x + 1.0, evacuate.y + 1.0, &view(Tab::Stats, 3, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_hit_spice_blow() {
+        let layout = Layout::new(1000.0, 600.0, 1.0, 1.0);
+        //Spice blow sits over the harvest button and takes the click
+        let harvest = layout.harvest;
+        let mut blow_view = view(Tab::Shop, 3, 0);
+        blow_view.spice_blow = Some((1.0, 0.0));
+        let blow = layout.spice_blow(1.0, 0.0);
+        assert!(blow.right() <= layout.panel.right());
+        assert_eq!(
+            layout.hit_test(blow.x + 1.0, blow.y + 1.0, &blow_view),
+            Some(UiAction::CollectSpiceBlow)
+        );
+        assert_eq!(
+            layout.hit_test(harvest.x + 1.0, harvest.bottom() - 1.0, &blow_view),
+            Some(UiAction::Harvest)
+        );
+        //Corners of the range stay inside the panel
+        let corner = layout.spice_blow(1.0, 1.0);
+        assert!(corner.bottom() <= layout.panel.bottom());
+    }
+
+    #[test]
+    fn test_buff_icons() {
+        let layout = Layout::new(1000.0, 600.0, 1.0, 1.0);
+        let icons = layout.buff_icons(2);
+        assert_eq!(icons.len(), 2);
+        //Icons fill in from the right, between the resources line and the tabs
+        assert_eq!(icons[0].right(), layout.panel.right());
+        assert!(icons[1].x < icons[0].x);
+        assert!(icons[0].y > layout.harvest.bottom());
+        assert!(icons[0].bottom() < layout.tabs[0].y);
+        let mut buff_view = view(Tab::Shop, 0, 0);
+        buff_view.num_buffs = 2;
+        assert_eq!(
+            layout.hit_test(icons[1].x + 1.0, icons[1].y + 1.0, &buff_view),
+            Some(UiAction::BuffIcon(1))
+        );
+        //Lots of buffs only get as many icons as there's room for
+        let many = layout.buff_icons(50);
+        assert!(many.len() < 50);
+        assert!(many.last().unwrap().x >= layout.panel.center().x);
+    }
+
+    #[test]
+    fn test_scrolled_shop_rows() {
+        let layout = Layout::new(1000.0, 600.0, 1.0, 1.0);
+        let first_row = layout.panel_row(1);
+        //With 5 lines scrolled away the top row is the 6th entry
+        assert_eq!(
+            layout.hit_test(
+                first_row.x + 5.0,
+                first_row.y + 5.0,
+                &view(Tab::Shop, 20, 5)
+            ),
+            Some(UiAction::BuyShopEntry(5))
+        );
+        //Rows below the panel can't be clicked even if there are more entries
+        let below = layout.panel_row(layout.visible_rows() + 1);
+        assert_eq!(
+            layout.hit_test(below.x + 5.0, below.y + 5.0, &view(Tab::Shop, 20, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_layout_scales_with_window() {
+        let small = Layout::new(1000.0, 600.0, 1.0, 1.0);
+        let big = Layout::new(2000.0, 1200.0, 1.0, 1.0);
+        //Harvest button stays in the top right corner
+        assert_eq!(small.harvest.right(), 1000.0 - OFFSET);
+        assert_eq!(big.harvest.right(), 2000.0 - OFFSET);
+        //Taller window fits more lines in the panel
+        assert!(big.visible_rows() > small.visible_rows());
+        //Bigger UI scale makes text bigger and fits fewer lines
+        let scaled = Layout::new(1000.0, 600.0, 1.5, 1.0);
+        assert_eq!(scaled.normal_text, NORMAL_TEXT_SIZE * 1.5);
+        assert!(scaled.visible_rows() < small.visible_rows());
+        //Tabs never spill past the right edge on a narrow window
+        let narrow = Layout::new(400.0, 600.0, 1.0, 1.0);
+        assert!(narrow.tabs[3].right() <= 400.0 - OFFSET);
+        //Goal bar sits under the Harvest button without running into the tabs
+        assert_eq!(small.goal_bar.x, small.harvest.x);
+        assert!(small.goal_bar.y > small.harvest.bottom());
+        assert!(small.goal_bar.bottom() < small.tabs[0].y);
+    }
+
+    #[test]
+    fn test_clamp_scroll() {
+        assert_eq!(clamp_scroll(0, 20, 5), 0);
+        assert_eq!(clamp_scroll(100, 20, 5), 15);
+        //Everything fits so there's nothing to scroll
+        assert_eq!(clamp_scroll(3, 4, 5), 0);
+    }
+
+    #[test]
+    fn test_paginate() {
+        let lines = vec!["a", "b", "c", "d", "e"];
+        assert_eq!(paginate(&lines, 1, 2), (vec!["a", "b"], 3));
+        assert_eq!(paginate(&lines, 3, 2), (vec!["e"], 3));
+        //Out of range pages are clamped
+        assert_eq!(paginate(&lines, 9, 2), (vec!["e"], 3));
+        assert_eq!(paginate(&[], 1, 2), (vec![], 1));
+    }
+}
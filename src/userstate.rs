@@ -1,12 +1,54 @@
+use crate::buffs::{Buff, BuffTarget, Buffs, Stacking};
+use crate::catalog;
 use crate::clickeritem::ClickerItem;
-use crate::item::Item;
+use crate::clickguard::{ClickAnalyzer, ClickLimits};
+use crate::clock::{STEP, SimClock};
+use crate::combo::Combo;
+use crate::contracts::{ContractContext, Contracts, Objective, Reward, is_rush_upgrade};
+use crate::events::{
+    ActiveEvent, EVACUATION_BONUS_SECONDS, EventKind, EventUpdate, Events, SANDWORM_TARGETS,
+    sandworm_losses,
+};
+use crate::factions::{Faction, PLEDGE_SPS};
+use crate::history::History;
+use crate::item::{Item, Reveal};
+use crate::market::{LimitOrder, Market, OrderSide, SPREAD};
+use crate::research::{Research, TechStatus, Technology, Unlock};
+use crate::resources::{Cost, MAX_QUALITY, QUALITY_PER_REFINERY};
+use crate::rng::Rng;
+use crate::spiceblow::{
+    ActiveBlow, BlowReward, CLICK_FRENZY_DURATION, CLICK_FRENZY_MULTIPLIER, FRENZY_DURATION,
+    FRENZY_MULTIPLIER, HARVEST_MINUTES, SpiceBlows,
+};
+use crate::strategy::{Choice, best_payback, format_time};
+use crate::synergy::synergy_bonus;
+use crate::weather::{Weather, WeatherCycle};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+//Spice needed on hand to prestige
+pub const PRESTIGE_SPICE: f64 = 1_000_000.0;
+//Permanent sps bonus for each prestige
+const PRESTIGE_BONUS: f64 = 0.1;
+//Frames longer than this are treated as a stall and caught up like offline time
+const MAX_FRAME_TIME: f64 = 5.0;
+//Longest step catching up takes at once, even when nothing is about to change
+const CATCH_UP_STEP: f64 = 60.0;
+//How many more of a building the shop tooltip prices up
+const BULK_PREVIEW: u32 = 10;
+//Most of a building bought at once, pricing them goes one at a time
+const MAX_BULK_BUY: u32 = 1000;
+
 #[derive(Serialize, Deserialize)]
 pub struct UserState {
     //Represents the amount of spice the player has
     spice: f64,
+    //Water collected by Sietches and Deathstills
+    #[serde(default)]
+    water: f64,
+    //Solari earned by selling spice to CHOAM
+    #[serde(default)]
+    solari: f64,
     //Represents the items the player can purchase
     items: Vec<Item>,
     //Represents the clicker items the player can purchase
@@ -19,6 +61,74 @@ pub struct UserState {
     time_last_updated: f64,
     //List of clicker items the user owns, used because clicker items are one-time purchases
     owned_clicker_items: Vec<ClickerItem>,
+    //Total number of harvest clicks, defaulted so older saves still load
+    #[serde(default)]
+    total_clicks: u64,
+    //Spice and sps over time for the graph
+    #[serde(default)]
+    history: History,
+    //Random events like sandworm attacks
+    #[serde(default)]
+    events: Events,
+    //Temporary multipliers on sps and spc
+    #[serde(default)]
+    buffs: Buffs,
+    //Bonus pickups that show up every few minutes
+    #[serde(default)]
+    spice_blows: SpiceBlows,
+    //Calm spells and Coriolis storms, the schedule comes from a seed saved with it
+    #[serde(default)]
+    weather: WeatherCycle,
+    //CHOAM's spice price and the player's limit orders
+    #[serde(default)]
+    market: Market,
+    //Faction the player pledged to this run, only cleared by prestiging
+    #[serde(default)]
+    faction: Option<Faction>,
+    //Whether the factions have already asked for a pledge this run
+    #[serde(default)]
+    pledge_offered: bool,
+    //Times the player has started over, each one permanently boosts sps
+    #[serde(default)]
+    prestige_level: u32,
+    //Finished and running research, kept through prestige
+    #[serde(default)]
+    research: Research,
+    //Contracts on offer and the ones the player accepted
+    #[serde(default)]
+    contracts: Contracts,
+    //All spice ever harvested, used to reveal shop entries
+    #[serde(default)]
+    lifetime_spice: f64,
+    //Whether clicking ever looked automated, which keeps the run off the leaderboard
+    #[serde(default)]
+    autoclick_flagged: bool,
+    //Spice the player is aiming to have on hand, cleared once it's reached
+    #[serde(default)]
+    goal: Option<f64>,
+    //Caps the click rate and watches for autoclickers, set up from the player's settings
+    #[serde(skip)]
+    click_analyzer: ClickAnalyzer,
+    //Turns frame times into fixed steps
+    #[serde(skip)]
+    clock: SimClock,
+    //Multiplier for clicking fast, only lasts while the game is open
+    #[serde(skip)]
+    combo: Combo,
+    //Things that happened since the game last checked, shown on screen then cleared
+    #[serde(skip)]
+    notices: Vec<String>,
+    //Stops messages being printed, for running the game with nobody watching
+    #[serde(skip)]
+    quiet: bool,
+    //Set when the goal is reached, until the window picks it up to play a sound
+    #[serde(skip)]
+    goal_reached: bool,
+}
+
+//Whether a frame took long enough that it's caught up instead of stepped through
+pub fn is_stall(dt: f64) -> bool {
+    dt.is_finite() && dt > MAX_FRAME_TIME
 }
 
 impl UserState {
@@ -26,6 +136,8 @@ impl UserState {
     pub fn new(items: Vec<Item>, clicker_items: Vec<ClickerItem>) -> UserState {
         UserState {
             spice: 0.0,
+            water: 0.0,
+            solari: 0.0,
             items,
             clicker_items,
             sps: 0.0,
@@ -36,9 +148,41 @@ impl UserState {
                 .unwrap()
                 .as_secs_f64(),
             owned_clicker_items: vec![],
+            total_clicks: 0,
+            history: History::default(),
+            events: Events::default(),
+            buffs: Buffs::default(),
+            spice_blows: SpiceBlows::default(),
+            weather: WeatherCycle::default(),
+            market: Market::default(),
+            faction: None,
+            pledge_offered: false,
+            prestige_level: 0,
+            research: Research::default(),
+            contracts: Contracts::default(),
+            lifetime_spice: 0.0,
+            autoclick_flagged: false,
+            goal: None,
+            click_analyzer: ClickAnalyzer::default(),
+            clock: SimClock::default(),
+            combo: Combo::default(),
+            notices: vec![],
+            quiet: false,
+            goal_reached: false,
         }
     }
 
+    //Seeds everything random in the game, so the same seed always plays out the same way
+    pub fn with_seed(mut self, seed: u64) -> UserState {
+        let mut rng = Rng::new(seed);
+        self.events = Events::new(rng.next_u64());
+        self.spice_blows = SpiceBlows::new(rng.next_u64());
+        self.weather = WeatherCycle::new(rng.next_u64());
+        self.market = Market::new(rng.next_u64());
+        self.contracts = Contracts::new(rng.next_u64());
+        self
+    }
+
     //Gets current spice amount
     pub fn get_spice(&self) -> f64 {
         self.spice
@@ -49,14 +193,383 @@ impl UserState {
         self.spice = amount;
     }
 
+    //Gets current water amount
+    pub fn get_water(&self) -> f64 {
+        self.water
+    }
+
+    //Gets current solari amount
+    pub fn get_solari(&self) -> f64 {
+        self.solari
+    }
+
+    //Gets water made per second by owned items
+    pub fn water_per_second(&self) -> f64 {
+        self.items
+            .iter()
+            .map(|item| item.get_amt() as f64 * item.get_water())
+            .sum()
+    }
+
+    //Gets melange quality, 1 is normal and every refinery makes it a little better
+    pub fn melange_quality(&self) -> f64 {
+        (1.0 + self.amount_owned("Spice Refinery") as f64 * QUALITY_PER_REFINERY).min(MAX_QUALITY)
+    }
+
     //Gets current spice per second rate
     pub fn get_sps(&self) -> f64 {
         self.sps
     }
 
-    //Gets current spice per click rate
+    //Gets current spice per click rate, including the share of sps from upgrades and any running buffs
+    //The combo isn't included since it changes from click to click
     pub fn get_spc(&self) -> f64 {
-        self.spc
+        let base = self.spc + self.sps * self.click_sps_share();
+        self.buffs.apply(&BuffTarget::Spc, base) * self.faction_multiplier(&BuffTarget::Spc)
+    }
+
+    //Gets the share of sps every click adds, from owned upgrades
+    fn click_sps_share(&self) -> f64 {
+        self.owned_clicker_items
+            .iter()
+            .map(|clicker_item| clicker_item.get_sps_share())
+            .sum()
+    }
+
+    //Sets how strict the click analyzer is
+    pub fn set_click_limits(&mut self, limits: ClickLimits) {
+        self.click_analyzer.set_limits(limits);
+    }
+
+    //Gets whether the run can go on the leaderboard, it can't once clicking looked automated
+    pub fn is_leaderboard_eligible(&self) -> bool {
+        !self.autoclick_flagged
+    }
+
+    //Gets the multiplier the next click gets from the combo
+    pub fn get_combo(&self) -> f64 {
+        self.combo.get_multiplier()
+    }
+
+    //Gets the faction the player pledged to, if any
+    pub fn get_faction(&self) -> Option<Faction> {
+        self.faction
+    }
+
+    //Gets the multiplier from the player's faction on a target, 1 if unpledged
+    fn faction_multiplier(&self, target: &BuffTarget) -> f64 {
+        self.faction
+            .map_or(1.0, |faction| faction.multiplier(target))
+    }
+
+    //Pledges to a faction, adding its unique buildings and clicker items to the shop
+    //Returns false if already pledged or sps isn't high enough yet
+    pub fn pledge(&mut self, faction: Faction) -> bool {
+        if self.faction.is_some() || self.sps < PLEDGE_SPS {
+            return false;
+        }
+        self.faction = Some(faction);
+        self.items.extend(faction.items());
+        self.clicker_items.extend(faction.clicker_items());
+        self.calculate_sps();
+        self.notify(format!("You have pledged yourself to {}", faction.name()));
+        true
+    }
+
+    //Starts researching a technology by name, paying its cost up front
+    //Returns false and says why if it can't be started
+    pub fn start_research(&mut self, name: &str) -> bool {
+        let Some(tech) = catalog::technologies()
+            .into_iter()
+            .find(|tech| tech.name.eq_ignore_ascii_case(name.trim()))
+        else {
+            self.say(&format!("No technology called {}", name.trim()));
+            return false;
+        };
+        if self.research.is_busy() {
+            self.say("Already researching something, wait for it to finish");
+            return false;
+        }
+        match self.research.status(&tech) {
+            TechStatus::Available => {}
+            TechStatus::Locked => {
+                self.say(&format!(
+                    "{} needs {}",
+                    tech.name,
+                    tech.prerequisites.join(" and ")
+                ));
+                return false;
+            }
+            _ => {
+                self.say(&format!("{} is already researched", tech.name));
+                return false;
+            }
+        }
+        if tech.cost as f64 > self.spice {
+            self.say(&format!("Not enough spice to research {}", tech.name));
+            return false;
+        }
+        self.spice -= tech.cost as f64;
+        self.research.start(&tech);
+        self.say(&format!("Started researching {}", tech.name));
+        true
+    }
+
+    //Whether a research-locked command has been unlocked
+    pub fn is_unlocked(&self, command: &str) -> bool {
+        catalog::technologies().iter().any(|tech| {
+            self.research.is_done(tech.name)
+                && tech
+                    .unlocks
+                    .iter()
+                    .any(|unlock| matches!(unlock, Unlock::Command(c) if *c == command))
+        })
+    }
+
+    //Moves research forward and hands out whatever it unlocks when it finishes
+    fn advance_research(&mut self, dt: f64) {
+        let Some(name) = self.research.advance(dt) else {
+            return;
+        };
+        if let Some(tech) = catalog::technologies()
+            .into_iter()
+            .find(|tech| tech.name == name)
+        {
+            let unlocks = tech.describe_unlocks();
+            self.apply_unlocks(tech);
+            self.notify(format!("Research complete: {}! Unlocked {}", name, unlocks));
+        }
+    }
+
+    //Adds what a technology unlocks to the shop, skipping anything already there
+    fn apply_unlocks(&mut self, tech: Technology) {
+        for unlock in tech.unlocks {
+            match unlock {
+                Unlock::Item(item) => {
+                    if !self.items.iter().any(|i| i.get_name() == item.get_name()) {
+                        self.items.push(item);
+                    }
+                }
+                Unlock::Upgrade(clicker_item) => {
+                    let known = self
+                        .clicker_items
+                        .iter()
+                        .chain(self.owned_clicker_items.iter())
+                        .any(|c| c.get_name() == clicker_item.get_name());
+                    if !known {
+                        self.clicker_items.push(clicker_item);
+                    }
+                }
+                Unlock::Command(_) => {}
+            }
+        }
+    }
+
+    //Lists every technology and where it stands
+    pub fn list_research(&self) -> String {
+        let mut research_text = String::from("---Research---\n");
+        for tech in catalog::technologies() {
+            let line = match self.research.status(&tech) {
+                TechStatus::Done => format!("[Done] {}", tech.name),
+                TechStatus::InProgress(time_left) => format!(
+                    "[Researching, {:.0}s left] {}: unlocks {}",
+                    time_left.ceil(),
+                    tech.name,
+                    tech.describe_unlocks()
+                ),
+                TechStatus::Available => format!(
+                    "[Available] {}: Cost: {}, {:.0}s, unlocks {}",
+                    tech.name,
+                    tech.cost,
+                    tech.duration,
+                    tech.describe_unlocks()
+                ),
+                TechStatus::Locked => format!(
+                    "[Locked] {}: needs {}",
+                    tech.name,
+                    tech.prerequisites.join(" and ")
+                ),
+            };
+            research_text.push_str(&line);
+            research_text.push('\n');
+        }
+        research_text
+    }
+
+    //Posts new contract offers, pays out finished contracts and penalizes ones that ran out of time
+    //Objectives are checked before the clocks run down, so anything done in time counts
+    fn advance_contracts(&mut self, dt: f64) {
+        let context = ContractContext {
+            sps: self.sps,
+            buildings: self
+                .items
+                .iter()
+                .filter(|item| item.is_revealed())
+                .map(|item| (item.get_name(), item.get_amt()))
+                .collect(),
+            has_rush_upgrade: self
+                .owned_clicker_items
+                .iter()
+                .any(|c| is_rush_upgrade(c.get_name())),
+        };
+        self.contracts.post_offers(dt, &context);
+        loop {
+            let (spice, clicks, items) = (self.spice, self.total_clicks, &self.items);
+            let owned = |name: &str| {
+                items
+                    .iter()
+                    .find(|item| item.get_name() == name)
+                    .map_or(0, |item| item.get_amt())
+            };
+            let Some(contract) = self
+                .contracts
+                .take_completed(|c| c.is_met(spice, clicks, owned))
+            else {
+                break;
+            };
+            if let Objective::Deliver { spice } = contract.objective {
+                self.spice -= spice;
+            }
+            let description = contract.describe();
+            match contract.reward {
+                Reward::Spice(amount) => self.earn(amount),
+                Reward::Solari(amount) => self.solari += amount,
+                Reward::Buff(buff) => self.buffs.add(buff, Stacking::Refresh),
+                Reward::Upgrade(clicker_item) => {
+                    self.spc *= clicker_item.get_multiplier();
+                    self.owned_clicker_items.push(clicker_item);
+                }
+            }
+            self.calculate_sps();
+            self.notify(format!("Contract complete! {}", description));
+        }
+        for contract in self.contracts.advance(dt) {
+            let lost = contract.penalty.min(self.spice);
+            self.spice -= lost;
+            self.notify(format!(
+                "Contract failed, {} took {:.0} spice",
+                contract.issuer.name(),
+                lost
+            ));
+        }
+    }
+
+    //Accepts a contract offer by its 0-based position on the board
+    //Returns false if there's no such offer or too many contracts are already running
+    pub fn accept_contract(&mut self, index: usize) -> bool {
+        self.contracts.accept(index, self.total_clicks)
+    }
+
+    //Lists accepted contracts with their progress, then the offers on the board
+    pub fn list_contracts(&self) -> String {
+        let mut contracts_text = String::from("---Contracts---\n");
+        let owned = |name: &str| self.amount_owned(name);
+        for contract in self.contracts.get_active() {
+            contracts_text.push_str(&format!(
+                "[{}] {}\n",
+                contract.progress(self.spice, self.total_clicks, owned),
+                contract.describe()
+            ));
+        }
+        if self.contracts.get_offers().is_empty() {
+            contracts_text.push_str("No offers right now\n");
+        }
+        for (idx, contract) in self.contracts.get_offers().iter().enumerate() {
+            contracts_text.push_str(&format!("Offer {}. {}\n", idx + 1, contract.describe()));
+        }
+        contracts_text
+    }
+
+    //Starts a new run with the given shop, keeping a permanent sps bonus
+    //Faction allegiance is cleared so a different one can be picked
+    //Returns false if there isn't enough spice yet
+    pub fn prestige(&mut self, items: Vec<Item>, clicker_items: Vec<ClickerItem>) -> bool {
+        if self.spice < PRESTIGE_SPICE {
+            return false;
+        }
+        self.prestige_level += 1;
+        self.spice = 0.0;
+        self.water = 0.0;
+        self.solari = 0.0;
+        self.items = items;
+        self.clicker_items = clicker_items;
+        self.owned_clicker_items.clear();
+        self.spc = 1.0;
+        self.buffs = Buffs::default();
+        self.market.cancel_orders();
+        self.faction = None;
+        self.pledge_offered = false;
+        //Research is kept, so everything it unlocked goes back in the shop
+        for tech in catalog::technologies() {
+            if self.research.is_done(tech.name) {
+                self.apply_unlocks(tech);
+            }
+        }
+        self.calculate_sps();
+        self.notify(format!(
+            "Prestige {}! Spice per second is permanently x{:.1}",
+            self.prestige_level,
+            self.prestige_multiplier()
+        ));
+        true
+    }
+
+    //Gets the permanent sps multiplier from prestiging
+    fn prestige_multiplier(&self) -> f64 {
+        1.0 + self.prestige_level as f64 * PRESTIGE_BONUS
+    }
+
+    //Gets the running buffs
+    pub fn get_buffs(&self) -> &Buffs {
+        &self.buffs
+    }
+
+    //Gets the spice blow on screen, if any
+    pub fn get_spice_blow(&self) -> Option<&ActiveBlow> {
+        self.spice_blows.get_active()
+    }
+
+    //Gets the recorded spice and sps history
+    pub fn get_history(&self) -> &History {
+        &self.history
+    }
+
+    //Gets the weather cycle
+    pub fn get_weather(&self) -> &WeatherCycle {
+        &self.weather
+    }
+
+    //Gets the spice market
+    pub fn get_market(&self) -> &Market {
+        &self.market
+    }
+
+    //Gets the random event waiting on the player, if any
+    pub fn get_active_event(&self) -> Option<&ActiveEvent> {
+        self.events.get_active()
+    }
+
+    //Takes the notices that haven't been shown yet
+    pub fn take_notices(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.notices)
+    }
+
+    //Turns printing messages off or back on
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
+    //Prints a message for the player unless the game is quiet
+    fn say(&self, message: &str) {
+        if !self.quiet {
+            println!("{}", message);
+        }
+    }
+
+    //Prints a notice and keeps it to be shown on screen
+    fn notify(&mut self, notice: String) {
+        self.say(&notice);
+        self.notices.push(notice);
     }
 
     //Gets last updated time
@@ -84,6 +597,64 @@ impl UserState {
         self.items.len() + self.num_clicker_items()
     }
 
+    //Rebuilds the shop from the catalog when loading, saves only have the amount of each item
+    //Prices, output and cost curves all come from the catalog, so saves from older versions
+    //get today's prices for what they own and anything added since shows up in catalog order
+    //Faction and research items are rebuilt the same way from the pledge and finished research
+    pub fn sync_catalog(&mut self, items: Vec<Item>, clicker_items: Vec<ClickerItem>) {
+        let mut definitions = items;
+        if let Some(faction) = self.faction {
+            definitions.extend(faction.items());
+        }
+        for tech in catalog::technologies() {
+            if self.research.is_done(tech.name) {
+                for unlock in tech.unlocks {
+                    if let Unlock::Item(item) = unlock {
+                        definitions.push(item);
+                    }
+                }
+            }
+        }
+        let saved = std::mem::take(&mut self.items);
+        for mut item in definitions {
+            if let Some(saved_item) = saved.iter().find(|s| s.get_name() == item.get_name()) {
+                item.restore_from(saved_item);
+            }
+            self.items.push(item);
+        }
+        //Nothing says what these make or cost anymore, so they can't be kept
+        for saved_item in saved.iter() {
+            if !self
+                .items
+                .iter()
+                .any(|i| i.get_name() == saved_item.get_name())
+            {
+                self.say(&format!(
+                    "{} no longer exists and was removed",
+                    saved_item.get_name()
+                ));
+            }
+        }
+        for clicker_item in clicker_items {
+            if let Some(saved) = self
+                .clicker_items
+                .iter_mut()
+                .find(|c| c.get_name() == clicker_item.get_name())
+            {
+                saved.refresh_from(&clicker_item);
+                continue;
+            }
+            let owned = self
+                .owned_clicker_items
+                .iter()
+                .any(|c| c.get_name() == clicker_item.get_name());
+            if !owned {
+                self.clicker_items.push(clicker_item);
+            }
+        }
+        self.calculate_sps();
+    }
+
     //List items in the user's inventory
     pub fn list_inventory(&self) -> String {
         let mut inventory_text = String::from("---Inventory---\n");
@@ -94,11 +665,21 @@ impl UserState {
         }
 
         //Iterate through items, listing any the user owns at least 1 of
+        //Each gets a second line splitting its output into base and what other buildings add
+        let synergies = catalog::synergies();
         for item in self.items.iter() {
             if item.get_amt() == 0 {
                 continue;
             }
-            inventory_text.push_str(&format!("{}\n", item.info_in_inventory()));
+            let base = item.get_amt() as f64 * item.get_worth();
+            let bonus = synergy_bonus(&synergies, item.get_name(), |name| self.amount_owned(name));
+            inventory_text.push_str(&format!(
+                "{}\n  Output: {:.2} base + {:.2} synergy (+{:.1}%)\n",
+                item.info_in_inventory(),
+                base,
+                base * bonus,
+                bonus * 100.0
+            ));
         }
         for clicker_item in self.owned_clicker_items.iter() {
             inventory_text.push_str(&format!("{}\n", clicker_item.info_in_inventory()));
@@ -109,67 +690,837 @@ impl UserState {
     //List items available in the shop
     pub fn list_shop(&self) -> String {
         let mut shop_text = String::from("---Shop---\n");
-        let mut idx = 1;
-        for item in self.items.iter() {
-            shop_text.push_str(&format!("{}. {}\n", idx, item.info_in_shop()));
-            idx += 1;
+        for (idx, entry) in self.shop_entries().iter().enumerate() {
+            shop_text.push_str(&format!("{}. {}\n", idx + 1, entry));
         }
+        shop_text
+    }
+
+    //Gets the shop line for every purchasable entry, items first then clicker items
+    //Entries that haven't been revealed yet show as ???
+    pub fn shop_entries(&self) -> Vec<String> {
+        let hidden = || String::from("???");
+        let best = self.best_value();
+        let mut entries: Vec<String> = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                if !item.is_revealed() {
+                    return hidden();
+                }
+                let mut entry = item.info_in_shop();
+                //How good a deal it is, only for buildings that make spice
+                let choice = self.choice_for(index, &[]);
+                if let Some(cost_per_sps) = choice.cost_per_sps() {
+                    entry.push_str(&format!(", {:.0} spice per sps", cost_per_sps));
+                    if let Some(payback) = choice.payback_time(self.spice, self.sps) {
+                        entry.push_str(&format!(", pays back in {}", format_time(payback)));
+                    }
+                }
+                if best == Some(index) {
+                    entry.push_str(" (best value)");
+                }
+                entry
+            })
+            .collect();
         for clicker_item in self.clicker_items.iter() {
-            shop_text.push_str(&format!("{}. {}\n", idx, clicker_item.info_in_shop()));
-            idx += 1;
+            if clicker_item.is_revealed() {
+                entries.push(clicker_item.info_in_shop());
+            } else {
+                entries.push(hidden());
+            }
+        }
+        entries
+    }
+
+    //Gets a hint for how to reveal a hidden shop entry
+    fn reveal_hint(reveal: &Reveal) -> String {
+        match reveal {
+            Reveal::Owns(building, count) => format!("Own {} {} to find out", count, building),
+            _ => String::from("Keep harvesting to find out"),
+        }
+    }
+
+    //Gets the hover text for a shop entry, None if the entry doesn't exist
+    pub fn shop_tooltip(&self, entry: usize) -> Option<String> {
+        if entry < self.items.len() {
+            let item = &self.items[entry];
+            if !item.is_revealed() {
+                return Some(format!(
+                    "???\n{}",
+                    UserState::reveal_hint(item.get_reveal())
+                ));
+            }
+            let mut tooltip = format!(
+                "{}\nOwned: {}\nAdds {:.2} spice per second",
+                item.get_name(),
+                item.get_amt(),
+                item.get_worth()
+            );
+            if item.get_water() > 0.0 {
+                tooltip.push_str(&format!("\nAdds {:.2} water per second", item.get_water()));
+            }
+            if let Some(missing) = self.shortfall(&item.get_cost()) {
+                tooltip.push_str(&format!("\nNeeds more {}", missing));
+            }
+            tooltip.push_str(&format!(
+                "\nNext {} cost {}",
+                BULK_PREVIEW,
+                item.total_price(item.get_amt(), BULK_PREVIEW)
+            ));
+            Some(tooltip)
+        } else {
+            let clicker_item = self.clicker_items.get(entry - self.items.len())?;
+            if !clicker_item.is_revealed() {
+                return Some(format!(
+                    "???\n{}",
+                    UserState::reveal_hint(clicker_item.get_reveal())
+                ));
+            }
+            let mut tooltip = format!(
+                "{}\nMultiplies spice per click by {}",
+                clicker_item.get_name(),
+                clicker_item.get_multiplier()
+            );
+            if clicker_item.get_sps_share() > 0.0 {
+                tooltip.push_str(&format!(
+                    "\nAdds {}% of spice per second to every click",
+                    clicker_item.get_sps_share() * 100.0
+                ));
+            }
+            let spc = (self.spc * clicker_item.get_multiplier())
+                + self.sps * (self.click_sps_share() + clicker_item.get_sps_share());
+            tooltip.push_str(&format!("\nClick would give {:.2}", spc));
+            Some(tooltip)
         }
-        shop_text
     }
 
-    //Calculates sps based on owned items
+    //List general stats about the run
+    pub fn list_stats(&self) -> String {
+        let buildings: u32 = self.items.iter().map(|item| item.get_amt()).sum();
+        format!(
+            "---Stats---\nBuildings owned: {}\nUpgrades owned: {}\nTotal clicks: {}\nSpice per click: {:.2}\nTime played: {:.0} minutes\nMelange quality: {:.0}%\nWeather: {} for {:.0} more minutes\nAllegiance: {}\nPrestige level: {}\nLeaderboard eligible: {}\n",
+            buildings,
+            self.owned_clicker_items.len(),
+            self.total_clicks,
+            self.spc,
+            self.history.get_clock() / 60.0,
+            self.melange_quality() * 100.0,
+            self.weather.current().name(),
+            (self.weather.get_time_left() / 60.0).ceil(),
+            self.faction.map_or("None", |faction| faction.name()),
+            self.prestige_level,
+            if self.is_leaderboard_eligible() {
+                "Yes"
+            } else {
+                "No, clicking looked automated"
+            }
+        )
+    }
+
+    //Calculates sps based on owned items, synergies, running buffs, faction and prestige
+    //Synergies and building buffs apply to that building's output first, then sps buffs apply to the total
     pub fn calculate_sps(&mut self) {
+        //Rounding to 2 decimal places since getting very long floats otherwise
+        self.sps = (self.sps_with(&[]) * 100.0).round() / 100.0;
+    }
+
+    //Works out sps as if planned[i] more of item i were owned, items past the end of planned add none
+    fn sps_with(&self, planned: &[u32]) -> f64 {
+        let synergies = catalog::synergies();
+        let owned = |name: &str| {
+            let index = self.items.iter().position(|item| item.get_name() == name);
+            let added = index.and_then(|index| planned.get(index)).copied();
+            self.amount_owned(name) + added.unwrap_or(0)
+        };
         let mut temp_sps = 0.0;
         for item in self.items.iter() {
-            temp_sps += item.get_amt() as f64 * item.get_worth();
+            let bonus = synergy_bonus(&synergies, item.get_name(), owned);
+            let output = owned(item.get_name()) as f64 * item.get_worth() * (1.0 + bonus);
+            let target = BuffTarget::Building(item.get_name().to_string());
+            temp_sps += self.buffs.apply(&target, output) * self.faction_multiplier(&target);
         }
-        self.sps = temp_sps;
-        //Rounding to 2 decimal places since getting very long floats otherwise
-        self.sps = (self.sps * 100.0).round() / 100.0;
+        self.buffs.apply(&BuffTarget::Sps, temp_sps)
+            * self.faction_multiplier(&BuffTarget::Sps)
+            * self.prestige_multiplier()
+    }
+
+    //Gets the price of the next one of an item after the planned purchases
+    fn planned_price(&self, index: usize, planned: &[u32]) -> Cost {
+        let item = &self.items[index];
+        let already = planned.get(index).copied().unwrap_or(0);
+        item.price_of_nth(item.get_amt() + already)
+    }
+
+    //Gets what buying one more of an item would cost and add to sps, after the planned purchases
+    fn choice_for(&self, index: usize, planned: &[u32]) -> Choice {
+        let mut with_one_more = planned.to_vec();
+        with_one_more.resize(self.items.len(), 0);
+        with_one_more[index] += 1;
+        Choice {
+            index,
+            name: self.items[index].get_name().to_string(),
+            cost: self.planned_price(index, planned).spice as f64,
+            sps_gain: self.sps_with(&with_one_more) - self.sps_with(planned),
+        }
+    }
+
+    //Gets the buildings that could be bought after the planned purchases, with what each would add to sps
+    //Ones still hidden, or needing more water or solari than is left after the planned purchases, are left out
+    fn choices_after(&self, planned: &[u32], water: f64, solari: f64) -> Vec<Choice> {
+        (0..self.items.len())
+            .filter(|&index| self.items[index].is_revealed())
+            .filter(|&index| {
+                let price = self.planned_price(index, planned);
+                price.water as f64 <= water && price.solari as f64 <= solari
+            })
+            .map(|index| self.choice_for(index, planned))
+            .collect()
     }
 
-    //Update spice after a click
-    pub fn update_spice_by_click(&mut self) {
-        self.spice += self.spc;
+    //Gets the buildings that could be bought next with what each would add to sps
+    pub fn purchase_choices(&self) -> Vec<Choice> {
+        self.choices_after(&[], self.water, self.solari)
     }
 
-    //Updates spice based on sps and time difference
+    //Gets the shop position of the building that pays for itself soonest, the same pick the simulator's payback bot makes
+    pub fn best_value(&self) -> Option<usize> {
+        best_payback(&self.purchase_choices(), self.spice, self.sps).map(|choice| choice.index)
+    }
+
+    //Plans the next count purchases, each the one that pays back soonest after the ones before it
+    //Assumes each is bought as soon as it's affordable, so the spice and sps it's judged on are what there'd be then
+    pub fn advise(&self, count: usize) -> Vec<(Choice, Option<f64>)> {
+        let mut planned = vec![0; self.items.len()];
+        let (mut spice, mut sps) = (self.spice, self.sps);
+        let (mut water, mut solari) = (self.water, self.solari);
+        let mut advice = vec![];
+        for _ in 0..count {
+            let choices = self.choices_after(&planned, water, solari);
+            let Some(choice) = best_payback(&choices, spice, sps) else {
+                break;
+            };
+            advice.push((choice.clone(), choice.payback_time(spice, sps)));
+            let price = self.planned_price(choice.index, &planned);
+            planned[choice.index] += 1;
+            spice = (spice - choice.cost).max(0.0);
+            water -= price.water as f64;
+            solari -= price.solari as f64;
+            sps += choice.sps_gain;
+        }
+        advice
+    }
+
+    //Gets the planned purchases as text to print
+    pub fn list_advice(&self, count: usize) -> String {
+        let advice = self.advise(count);
+        if advice.is_empty() {
+            return String::from("Nothing worth buying yet\n");
+        }
+        let mut text = format!("---Next {} purchases---\n", advice.len());
+        for (number, (choice, payback)) in advice.iter().enumerate() {
+            let payback = payback.map_or(String::from("unknown"), format_time);
+            text.push_str(&format!(
+                "{}. {} for {:.0} spice, +{:.2} spice per second, pays back in {}\n",
+                number + 1,
+                choice.name,
+                choice.cost,
+                choice.sps_gain,
+                payback
+            ));
+        }
+        text
+    }
+
+    //Update spice after a click, returns how much spice the click gave
+    //Clicks over the rate cap give nothing and don't count
+    pub fn update_spice_by_click(&mut self) -> f64 {
+        let credited = self.click_analyzer.click();
+        if self.click_analyzer.is_flagged() && !self.autoclick_flagged {
+            self.autoclick_flagged = true;
+            self.notify(String::from(
+                "Clicking looks automated, this run can no longer go on the leaderboard",
+            ));
+        }
+        if !credited {
+            return 0.0;
+        }
+        let amount = self.get_spc() * self.combo.click();
+        self.earn(amount);
+        self.total_clicks += 1;
+        amount
+    }
+
+    //Updates spice based on sps and time difference, and runs random events
     pub fn update_spice(&mut self, dt: f64) {
         if !dt.is_finite() || dt <= 0.0 {
             return;
         }
+        self.combo.advance(dt);
+        self.click_analyzer.advance(dt);
+        if self.simulate(dt) > 0 {
+            let notice = match self.weather.current() {
+                Weather::Calm => "The Coriolis storm has passed and the skies are calm",
+                Weather::CoriolisStorm => {
+                    "A Coriolis storm is sweeping the fields! Ornithopters are grounded"
+                }
+            };
+            self.notify(notice.to_string());
+        }
+        let can_start = SANDWORM_TARGETS
+            .iter()
+            .any(|name| self.amount_owned(name) > 0);
+        match self.events.advance(dt, can_start) {
+            Some(EventUpdate::Started(kind)) => self.notify(kind.warning().to_string()),
+            Some(EventUpdate::Expired(EventKind::Sandworm)) => self.sandworm_attack(),
+            None => {}
+        }
+        if self.faction.is_none() && !self.pledge_offered && self.sps >= PLEDGE_SPS {
+            self.pledge_offered = true;
+            self.notify(String::from(
+                "The great powers seek your allegiance! Type pledge to see who you can join",
+            ));
+        }
+        if self.spice_blows.advance(dt) {
+            self.notify(String::from(
+                "A spice blow erupted! Click it before it settles",
+            ));
+        }
+        self.reveal_shop();
+        self.check_goal();
+    }
+
+    //Runs a frame, the game itself only ever moves in fixed steps so
+    //buffs, research and everything else come out the same at any frame rate
+    //A frame long enough to be a stall, like the computer sleeping, is caught up like time spent closed
+    //Returns how many steps it ran, a stall counts as none since it isn't stepped through
+    pub fn update(&mut self, dt: f64) -> u32 {
+        if !dt.is_finite() || dt <= 0.0 {
+            return 0;
+        }
+        if is_stall(dt) {
+            self.catch_up(dt);
+            return 0;
+        }
+        let steps = self.clock.advance(dt);
+        for _ in 0..steps {
+            self.step();
+        }
+        steps
+    }
+
+    //Runs one fixed step of the game
+    pub fn step(&mut self) {
+        self.update_spice(STEP);
+    }
+
+    //Catches up on time spent with the game closed
+    //Random events and spice blows are paused since the player couldn't respond to them
+    //Weather, research, contracts and the market keep going, in steps as long as nothing changes
+    //so it ends up where it would have if the game had been open the whole time
+    pub fn catch_up(&mut self, dt: f64) {
+        if !dt.is_finite() || dt <= 0.0 {
+            return;
+        }
+        let mut remaining = dt;
+        while remaining > 0.0 {
+            let step = self.next_change().clamp(STEP, CATCH_UP_STEP).min(remaining);
+            self.simulate(step);
+            remaining -= step;
+        }
+        self.reveal_shop();
+        self.check_goal();
+    }
+
+    //Moves everything that doesn't need the player forward, returns how many times the weather changed
+    //Used both while playing and while catching up, which is what keeps the two the same
+    fn simulate(&mut self, dt: f64) -> u32 {
+        let weather_changes = self.produce(dt);
+        self.advance_research(dt);
+        self.advance_contracts(dt);
+        self.trade(dt);
+        weather_changes
+    }
+
+    //Seconds until something other than spice piling up happens, catching up can jump this far at once
+    //Buffs running out and the weather changing are split on inside produce
+    fn next_change(&self) -> f64 {
+        let mut next = self.market.next_tick().min(self.contracts.next_change());
+        if let Some(time_left) = self.research.time_left() {
+            next = next.min(time_left);
+        }
+        //Deliveries complete the moment there's enough spice
+        for contract in self.contracts.get_active() {
+            if let Objective::Deliver { spice } = contract.objective
+                && spice > self.spice
+                && self.sps > 0.0
+            {
+                next = next.min((spice - self.spice) / self.sps);
+            }
+        }
+        next
+    }
+
+    //Sets a spice goal, replacing any there was
+    //Returns false if it isn't more than the spice on hand already
+    pub fn set_goal(&mut self, amount: f64) -> bool {
+        if !amount.is_finite() || amount <= self.spice {
+            return false;
+        }
+        self.goal = Some(amount);
+        true
+    }
+
+    //Drops the goal, returns false if there wasn't one
+    pub fn clear_goal(&mut self) -> bool {
+        self.goal.take().is_some()
+    }
+
+    //Get the spice goal
+    pub fn get_goal(&self) -> Option<f64> {
+        self.goal
+    }
+
+    //Gets how far spice on hand is towards the goal, from 0 to 1
+    pub fn goal_progress(&self) -> Option<f64> {
+        self.goal.map(|goal| (self.spice / goal).clamp(0.0, 1.0))
+    }
+
+    //Gets how many seconds until the goal at the current sps, None without a goal or any sps
+    pub fn goal_eta(&self) -> Option<f64> {
+        let goal = self.goal?;
+        (self.sps > 0.0).then(|| (goal - self.spice).max(0.0) / self.sps)
+    }
+
+    //Gets a line about the goal for the player
+    pub fn describe_goal(&self) -> String {
+        let (Some(goal), Some(progress)) = (self.goal, self.goal_progress()) else {
+            return String::from("No goal set, use goal <amount> to set one");
+        };
+        let eta = match self.goal_eta() {
+            Some(eta) => format!("about {} to go", format_time(eta)),
+            None => String::from("no spice per second to get there with yet"),
+        };
+        format!(
+            "Goal: {:.0} spice, {:.0}% there, {}",
+            goal,
+            progress * 100.0,
+            eta
+        )
+    }
+
+    //Takes whether the goal was reached since last asked
+    pub fn take_goal_reached(&mut self) -> bool {
+        std::mem::take(&mut self.goal_reached)
+    }
+
+    //Announces the goal once there's enough spice on hand, and clears it
+    fn check_goal(&mut self) {
+        if let Some(goal) = self.goal
+            && self.spice >= goal
+        {
+            self.goal = None;
+            self.goal_reached = true;
+            self.notify(format!("Goal reached! You have {:.0} spice", goal));
+        }
+    }
+
+    //Get all the spice ever harvested
+    pub fn get_lifetime_spice(&self) -> f64 {
+        self.lifetime_spice
+    }
+
+    //Adds harvested spice, counting it towards lifetime spice too
+    fn earn(&mut self, amount: f64) {
+        self.spice += amount;
+        self.lifetime_spice += amount;
+    }
+
+    //Reveals shop entries whose rule is now met, announcing the ones the player had to work for
+    //Spice on hand counts too, so saves from before lifetime spice was tracked don't start hidden
+    fn reveal_shop(&mut self) {
+        let lifetime_spice = self.lifetime_spice.max(self.spice);
+        let owned = |name: &str| self.amount_owned(name);
+        let items: Vec<usize> = (0..self.items.len())
+            .filter(|&i| {
+                let item = &self.items[i];
+                !item.is_revealed()
+                    && item
+                        .get_reveal()
+                        .is_met(lifetime_spice, item.get_cost().spice, owned)
+            })
+            .collect();
+        let clicker_items: Vec<usize> = (0..self.clicker_items.len())
+            .filter(|&i| {
+                let clicker_item = &self.clicker_items[i];
+                !clicker_item.is_revealed()
+                    && clicker_item.get_reveal().is_met(
+                        lifetime_spice,
+                        clicker_item.get_cost(),
+                        owned,
+                    )
+            })
+            .collect();
+        let mut found = vec![];
+        for i in items {
+            let item = &mut self.items[i];
+            item.reveal();
+            if *item.get_reveal() != Reveal::Always {
+                found.push(item.get_name().to_string());
+            }
+        }
+        for i in clicker_items {
+            let clicker_item = &mut self.clicker_items[i];
+            clicker_item.reveal();
+            if *clicker_item.get_reveal() != Reveal::Always {
+                found.push(clicker_item.get_name().to_string());
+            }
+        }
+        for name in found {
+            self.notify(format!("New in the shop: {}", name));
+        }
+    }
+
+    //Moves the market forward one price change at a time, filling limit orders as it goes
+    fn trade(&mut self, dt: f64) {
+        let mut remaining = dt;
+        while remaining >= self.market.next_tick() {
+            let step = self.market.next_tick();
+            self.market.advance(step);
+            remaining -= step;
+            self.fill_orders();
+        }
+        self.market.advance(remaining);
+    }
+
+    //Goes through with any limit orders the current sell or buy price triggers
+    //Orders there isn't enough spice or solari for are dropped
+    fn fill_orders(&mut self) {
+        let triggered = self
+            .market
+            .take_triggered(self.sell_price(), self.buy_price());
+        for order in triggered {
+            let filled = match order.side {
+                OrderSide::Sell => self.sell_spice(order.amount),
+                OrderSide::Buy => self.buy_spice(order.amount),
+            };
+            if !filled {
+                self.notify(format!(
+                    "Limit order to {} cancelled, not enough to pay for it",
+                    order.describe()
+                ));
+            }
+        }
+    }
+
+    //Adds spice made over dt and records it for the graph, returns how many times the weather changed
+    //Split up wherever a buff runs out so long steps don't get the buff the whole time
+    //Weather effects are buffs lasting until the weather changes, so that splits steps too
+    fn produce(&mut self, dt: f64) -> u32 {
+        let mut remaining = dt;
+        let mut weather_changes = 0;
+        while remaining > 0.0 {
+            self.apply_weather();
+            let step = self
+                .buffs
+                .next_expiry()
+                .map_or(remaining, |expiry| expiry.clamp(0.0, remaining));
+            self.calculate_sps();
+            self.earn(self.sps * step);
+            self.water += self.water_per_second() * step;
+            self.history.record(step, self.spice, self.sps);
+            self.buffs.advance(step);
+            weather_changes += self.weather.advance(step);
+            remaining -= step;
+        }
+        self.apply_weather();
         self.calculate_sps();
-        self.spice += self.sps * dt;
+        weather_changes
+    }
+
+    //Starts the current weather's buffs if they aren't running yet
+    fn apply_weather(&mut self) {
+        let weather = self.weather.current();
+        let buffs = weather.buffs(self.weather.get_time_left());
+        let running = buffs
+            .iter()
+            .all(|buff| self.buffs.get_active().iter().any(|b| b.name == buff.name));
+        if running {
+            return;
+        }
+        for buff in buffs {
+            self.buffs.add(buff, Stacking::Refresh);
+        }
+    }
+
+    //Collects the spice blow on screen, returns false if there isn't one
+    pub fn collect_spice_blow(&mut self) -> bool {
+        let Some(reward) = self.spice_blows.collect() else {
+            return false;
+        };
+        match reward {
+            BlowReward::Harvest => {
+                //Early on sps is tiny, so make sure it's worth a few clicks at least
+                let bonus = (self.sps * 60.0 * HARVEST_MINUTES).max(self.get_spc() * 13.0);
+                self.earn(bonus);
+                self.notify(format!("Spice blow! Harvested {:.2} spice", bonus));
+            }
+            BlowReward::Frenzy => {
+                self.buffs.add(
+                    Buff::multiply(
+                        "Frenzy",
+                        BuffTarget::Sps,
+                        FRENZY_MULTIPLIER,
+                        FRENZY_DURATION,
+                    ),
+                    Stacking::Refresh,
+                );
+                self.calculate_sps();
+                self.notify(format!(
+                    "Frenzy! Spice per second x{} for {} seconds",
+                    FRENZY_MULTIPLIER, FRENZY_DURATION
+                ));
+            }
+            BlowReward::ClickFrenzy => {
+                self.buffs.add(
+                    Buff::multiply(
+                        "Click Frenzy",
+                        BuffTarget::Spc,
+                        CLICK_FRENZY_MULTIPLIER,
+                        CLICK_FRENZY_DURATION,
+                    ),
+                    Stacking::Refresh,
+                );
+                self.notify(format!(
+                    "Click frenzy! Spice per click x{} for {} seconds",
+                    CLICK_FRENZY_MULTIPLIER, CLICK_FRENZY_DURATION
+                ));
+            }
+        }
+        true
+    }
+
+    //Gets how many of an item the player owns by name, 0 if it doesn't exist
+    pub fn amount_owned(&self, name: &str) -> u32 {
+        self.items
+            .iter()
+            .find(|item| item.get_name() == name)
+            .map_or(0, |item| item.get_amt())
+    }
+
+    //Player didn't evacuate, so the worm takes some of the targeted buildings
+    fn sandworm_attack(&mut self) {
+        let mut losses = vec![];
+        for item in self.items.iter_mut() {
+            if SANDWORM_TARGETS.contains(&item.get_name()) {
+                let lost = item.lose(sandworm_losses(item.get_amt()));
+                if lost > 0 {
+                    losses.push(format!("{} {}", lost, item.get_name()));
+                }
+            }
+        }
+        self.calculate_sps();
+        if losses.is_empty() {
+            self.notify(String::from("The sandworm passed without finding anything"));
+        } else {
+            self.notify(format!("A sandworm swallowed {}", losses.join(" and ")));
+        }
+    }
+
+    //Responds to the current random event, returns false if there's nothing to respond to
+    pub fn evacuate(&mut self) -> bool {
+        match self.events.resolve() {
+            Some(EventKind::Sandworm) => {
+                let bonus = self.sps * EVACUATION_BONUS_SECONDS;
+                self.earn(bonus);
+                self.notify(format!(
+                    "Evacuated in time! Crews recovered {:.2} spice",
+                    bonus
+                ));
+                true
+            }
+            None => false,
+        }
+    }
+
+    //Gets the solari CHOAM pays per spice, better melange fetches a better price
+    pub fn sell_price(&self) -> f64 {
+        self.market.get_price() * self.melange_quality()
+    }
+
+    //Gets the solari CHOAM charges per spice, the same grade as the player's plus a markup
+    pub fn buy_price(&self) -> f64 {
+        self.sell_price() * (1.0 + SPREAD)
+    }
+
+    //Sells spice to CHOAM for solari at the market price
+    //Returns false if there isn't that much spice to sell
+    pub fn sell_spice(&mut self, amount: f64) -> bool {
+        if !amount.is_finite() || amount <= 0.0 || amount > self.spice {
+            return false;
+        }
+        let earned = amount * self.sell_price();
+        self.spice -= amount;
+        self.solari += earned;
+        self.notify(format!(
+            "Sold {:.2} spice to CHOAM for {:.2} solari",
+            amount, earned
+        ));
+        true
+    }
+
+    //Buys spice from CHOAM with solari at the market price
+    //Returns false if there isn't enough solari
+    pub fn buy_spice(&mut self, amount: f64) -> bool {
+        let cost = amount * self.buy_price();
+        if !amount.is_finite() || amount <= 0.0 || cost > self.solari {
+            return false;
+        }
+        self.solari -= cost;
+        self.spice += amount;
+        self.notify(format!(
+            "Bought {:.2} spice from CHOAM for {:.2} solari",
+            amount, cost
+        ));
+        true
+    }
+
+    //Places a limit order that goes through once the price it would trade at reaches the limit
+    //Returns false if the amount or price make no sense
+    pub fn place_order(&mut self, side: OrderSide, amount: f64, price: f64) -> bool {
+        let valid = |n: f64| n.is_finite() && n > 0.0;
+        if !valid(amount) || !valid(price) {
+            return false;
+        }
+        self.market.place_order(LimitOrder {
+            side,
+            amount,
+            price,
+        });
+        true
+    }
+
+    //Cancels every limit order, returns how many there were
+    pub fn cancel_orders(&mut self) -> usize {
+        self.market.cancel_orders()
+    }
+
+    //Lists the trade prices and how many limit orders are waiting
+    pub fn list_market(&self) -> String {
+        format!(
+            "---CHOAM Market---\nSell at {:.4}, buy at {:.4} solari per spice, {} open orders\n",
+            self.sell_price(),
+            self.buy_price(),
+            self.market.get_orders().len()
+        )
+    }
+
+    //Gets the first resource there isn't enough of to pay a cost, None if it's affordable
+    fn shortfall(&self, cost: &Cost) -> Option<&'static str> {
+        if cost.spice as f64 > self.spice {
+            Some("spice")
+        } else if cost.water as f64 > self.water {
+            Some("water")
+        } else if cost.solari as f64 > self.solari {
+            Some("solari")
+        } else {
+            None
+        }
     }
 
     //Attempts to buy an item from the shop
     pub fn buy_item(&mut self, item_index: usize) {
-        let item = &mut self.items[item_index];
-        //Check if enough spice to buy the item
-        if item.get_cost() as f64 > self.spice {
-            println!("Not enough spice to purchase {}", item.get_name());
+        self.buy_items(item_index, 1);
+    }
+
+    //Attempts to buy several of an item at once, each priced along the item's cost curve
+    //Nothing is bought unless all of them can be paid for
+    pub fn buy_items(&mut self, item_index: usize, count: u32) {
+        let item = &self.items[item_index];
+        if !item.is_revealed() {
+            self.say("You haven't discovered that yet");
+            return;
+        }
+        if count == 0 {
+            return;
+        }
+        if count > MAX_BULK_BUY {
+            self.say(&format!("You can buy at most {} at once", MAX_BULK_BUY));
+            return;
+        }
+        let label = if count == 1 {
+            item.get_name().to_string()
+        } else {
+            format!("{} {}", count, item.get_name())
+        };
+        let cost = item.total_price(item.get_amt(), count);
+        //Check if there's enough of every resource to buy them all
+        if let Some(missing) = self.shortfall(&cost) {
+            self.say(&format!("Not enough {} to purchase {}", missing, label));
+        } else {
+            self.spice -= cost.spice as f64;
+            self.water -= cost.water as f64;
+            self.solari -= cost.solari as f64;
+            self.say(&format!("Purchased {}", label));
+            let item = &mut self.items[item_index];
+            for _ in 0..count {
+                item.purchase();
+            }
+            self.history.add_marker(item.get_name());
+            //Owning more might reveal something new
+            self.reveal_shop();
+        }
+    }
+
+    //Attempts to buy a shop entry by its 0-based position in the shop list
+    //Returns false if there is no entry at that position
+    pub fn buy_shop_entry(&mut self, entry: usize) -> bool {
+        if entry < self.num_items() {
+            self.buy_item(entry);
+            true
+        } else if entry < self.total_num_items() {
+            self.buy_clicker_item(entry - self.num_items());
+            true
         } else {
-            self.spice -= item.get_cost() as f64;
-            item.purchase();
-            println!("Purchased {}", item.get_name());
+            false
+        }
+    }
+
+    //Attempts to buy count of a shop entry by its 0-based position in the shop list
+    //Buildings can be bought several at a time, clicker items are only ever bought once
+    //Returns false if there is no entry at that position
+    pub fn buy_shop_entries(&mut self, entry: usize, count: u32) -> bool {
+        if entry < self.num_items() {
+            self.buy_items(entry, count);
+            true
+        } else if entry < self.total_num_items() {
+            self.buy_clicker_item(entry - self.num_items());
+            true
+        } else {
+            false
         }
     }
 
     //Attempts to buy a clicker item from the shop
     pub fn buy_clicker_item(&mut self, clicker_item_index: usize) {
-        let clicker_item = &mut self.clicker_items[clicker_item_index];
+        let clicker_item = &self.clicker_items[clicker_item_index];
+        if !clicker_item.is_revealed() {
+            self.say("You haven't discovered that yet");
+            return;
+        }
         //Check if enough spice to buy the clicker item
         if clicker_item.get_cost() as f64 > self.spice {
-            println!("Not enough spice to purchase {}", clicker_item.get_name());
+            self.say(&format!(
+                "Not enough spice to purchase {}",
+                clicker_item.get_name()
+            ));
         } else {
             //Purchase the clicker item, set it to owned, and increase spc
             self.spice -= clicker_item.get_cost() as f64;
             self.spc *= clicker_item.get_multiplier();
-            println!("Purchased {}", clicker_item.get_name());
+            self.say(&format!("Purchased {}", clicker_item.get_name()));
+            self.history.add_marker(clicker_item.get_name());
             self.owned_clicker_items.push(clicker_item.clone());
             self.clicker_items.remove(clicker_item_index);
         }
@@ -179,6 +1530,8 @@ impl UserState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::costcurve::CostCurve;
+    use crate::strategy::Strategy;
 
     #[test]
     fn test_sps() {
@@ -246,6 +1599,775 @@ mod tests {
         assert_eq!(game_state.owned_clicker_items.len(), 1);
     }
 
+    #[test]
+    fn test_purchase_marks_history() {
+        let items = vec![Item::new("Tools", 0, 1.0, 10)];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.set_spice(10.0);
+        game_state.buy_item(0);
+        game_state.update_spice(5.0);
+        let history = game_state.get_history();
+        assert_eq!(history.get_clock(), 5.0);
+        let markers = history.markers(crate::history::GraphRange::Minute);
+        assert_eq!(markers.len(), 1);
+        assert_eq!(markers[0].1.label, "Tools");
+    }
+
+    #[test]
+    fn test_buy_with_cost_bundle() {
+        let cost = Cost {
+            spice: 100,
+            water: 10,
+            solari: 5,
+        };
+        let items = vec![Item::with_cost("Refinery", 0, 1.0, cost).making_water(2.0)];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.set_spice(1000.0);
+        game_state.buy_item(0);
+        //Plenty of spice but no water or solari
+        assert_eq!(game_state.amount_owned("Refinery"), 0);
+        game_state.water = 10.0;
+        game_state.solari = 5.0;
+        game_state.buy_item(0);
+        assert_eq!(game_state.amount_owned("Refinery"), 1);
+        assert_eq!(game_state.get_spice(), 900.0);
+        assert_eq!(game_state.get_water(), 0.0);
+        assert_eq!(game_state.get_solari(), 0.0);
+        game_state.catch_up(10.0);
+        assert_eq!(game_state.get_water(), 20.0);
+    }
+
+    #[test]
+    fn test_sell_and_buy_spice() {
+        let items = vec![Item::new("Spice Refinery", 2, 40.0, 60000)];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.market = Market::new(6);
+        game_state.set_spice(1000.0);
+        assert!(!game_state.sell_spice(2000.0));
+        assert!(!game_state.sell_spice(-1.0));
+        assert!(game_state.sell_spice(1000.0));
+        assert_eq!(game_state.get_spice(), 0.0);
+        //Two refineries make the melange worth 10% more
+        let price = game_state.get_market().get_price();
+        assert!((game_state.get_solari() - 1000.0 * price * 1.1).abs() < 1e-9);
+        //Buying it back costs more than it sold for
+        assert!(!game_state.buy_spice(1000.0));
+        assert!(game_state.buy_spice(500.0));
+        assert_eq!(game_state.get_spice(), 500.0);
+        assert!(game_state.get_solari() < 1000.0 * price * 1.1 / 2.0);
+    }
+
+    #[test]
+    fn test_limit_orders_fill_offline() {
+        let mut game_state = UserState::new(vec![], vec![]);
+        game_state.market = Market::new(12);
+        game_state.set_spice(100.0);
+        //Any price will do for the sell, the buy needs solari from the sell
+        assert!(game_state.place_order(OrderSide::Sell, 100.0, 0.0001));
+        assert!(game_state.place_order(OrderSide::Buy, 1.0, 1.0));
+        assert!(!game_state.place_order(OrderSide::Buy, -1.0, 1.0));
+        game_state.catch_up(crate::market::TICK);
+        assert!(game_state.get_market().get_orders().is_empty());
+        assert_eq!(game_state.get_spice(), 1.0);
+        assert!(game_state.get_solari() > 0.0);
+        //Orders that can't be paid for are dropped
+        assert!(game_state.place_order(OrderSide::Sell, 50.0, 0.0001));
+        game_state.catch_up(crate::market::TICK);
+        assert!(game_state.get_market().get_orders().is_empty());
+        assert_eq!(game_state.get_spice(), 1.0);
+    }
+
+    #[test]
+    fn test_buy_order_stays_under_limit() {
+        let mut game_state = UserState::new(vec![], vec![]);
+        game_state.market = Market::new(12);
+        game_state.solari = 100.0;
+        //Just above the market price, which the markup alone would go over
+        let limit = game_state.get_market().get_price() * 1.05;
+        assert!(game_state.place_order(OrderSide::Buy, 100.0, limit));
+        for _ in 0..360 {
+            if game_state.get_market().get_orders().is_empty() {
+                break;
+            }
+            game_state.catch_up(crate::market::TICK);
+        }
+        assert!(game_state.get_market().get_orders().is_empty());
+        assert_eq!(game_state.get_spice(), 100.0);
+        let paid = (100.0 - game_state.get_solari()) / 100.0;
+        assert!(paid <= limit);
+    }
+
+    #[test]
+    fn test_buy_shop_entry() {
+        let items = vec![Item::new("Tools", 0, 1.0, 10)];
+        let clicker_items = vec![ClickerItem::new("Test Item 1", 2.0, 100)];
+        let mut game_state = UserState::new(items, clicker_items);
+        game_state.set_spice(200.0);
+        //Entry 1 is the first clicker item since items are listed first
+        assert!(game_state.buy_shop_entry(1));
+        assert_eq!(game_state.owned_clicker_items.len(), 1);
+        assert!(game_state.buy_shop_entry(0));
+        assert_eq!(game_state.items[0].get_amt(), 1);
+        assert!(!game_state.buy_shop_entry(1));
+        assert_eq!(game_state.get_spice(), 90.0);
+    }
+
+    #[test]
+    fn test_sandworm_ignored() {
+        let items = vec![
+            Item::new("Fremen", 4, 1.0, 100),
+            Item::new("Spice Harvester", 8, 47.0, 12000),
+        ];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.events = Events::new(3);
+        game_state.events.start(EventKind::Sandworm);
+        //Waiting out the warning loses a quarter of the harvesters
+        game_state.update_spice(EventKind::Sandworm.warning_time());
+        assert!(game_state.get_active_event().is_none());
+        assert_eq!(game_state.amount_owned("Spice Harvester"), 6);
+        assert_eq!(game_state.amount_owned("Fremen"), 4);
+        assert_eq!(game_state.take_notices().len(), 1);
+    }
+
+    #[test]
+    fn test_sandworm_evacuated() {
+        let items = vec![Item::new("Ornithopter", 2, 8.0, 1100)];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.events = Events::new(3);
+        assert!(!game_state.evacuate());
+        game_state.events.start(EventKind::Sandworm);
+        game_state.update_spice(1.0);
+        let spice_before = game_state.get_spice();
+        assert!(game_state.evacuate());
+        //Bonus is a minute of production and nothing is lost
+        //Weather always starts calm, and clear skies speed ornithopters up by a quarter
+        assert_eq!(
+            game_state.get_spice(),
+            spice_before + 20.0 * EVACUATION_BONUS_SECONDS
+        );
+        assert_eq!(game_state.amount_owned("Ornithopter"), 2);
+    }
+
+    #[test]
+    fn test_catch_up_pauses_events() {
+        let items = vec![Item::new("Spice Harvester", 4, 47.0, 12000)];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.events = Events::new(3);
+        game_state.catch_up(100000.0);
+        assert!(game_state.get_active_event().is_none());
+        assert_eq!(game_state.amount_owned("Spice Harvester"), 4);
+        //Weather kept changing the whole time, so it's somewhere between all storm and all calm
+        let base = 47.0 * 4.0 * 100000.0;
+        assert!(game_state.get_spice() > base * 0.75);
+        assert!(game_state.get_spice() < base * 1.1);
+    }
+
+    #[test]
+    fn test_storm_shifts_production() {
+        let items = vec![
+            Item::new("Ornithopter", 2, 8.0, 1100),
+            Item::new("Storm Shelter", 1, 90.0, 40000),
+        ];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.weather = WeatherCycle::new(5);
+        game_state.update_spice(1.0);
+        //Calm: ornithopters x1.25, shelter unchanged
+        assert_eq!(game_state.get_sps(), 110.0);
+        game_state.take_notices();
+        let calm_left = game_state.get_weather().get_time_left();
+        game_state.update_spice(calm_left);
+        assert_eq!(game_state.get_weather().current(), Weather::CoriolisStorm);
+        let notices = game_state.take_notices();
+        assert!(notices.iter().any(|n| n.starts_with("A Coriolis storm")));
+        //Storm: ornithopters halved, shelter tripled
+        assert_eq!(game_state.get_sps(), 278.0);
+    }
+
+    #[test]
+    fn test_weather_same_online_and_offline() {
+        let items = || vec![Item::new("Spice Harvester", 3, 47.0, 12000)];
+        let mut online = UserState::new(items(), vec![]);
+        let mut offline = UserState::new(items(), vec![]);
+        online.weather = WeatherCycle::new(9);
+        offline.weather = WeatherCycle::new(9);
+        for _ in 0..3600 {
+            online.produce(1.0);
+        }
+        offline.catch_up(3600.0);
+        assert_eq!(
+            online.get_weather().current(),
+            offline.get_weather().current()
+        );
+        assert!(
+            (online.get_weather().get_time_left() - offline.get_weather().get_time_left()).abs()
+                < 1e-6
+        );
+        assert!((online.get_spice() - offline.get_spice()).abs() < 1e-6 * offline.get_spice());
+    }
+
+    //Buildings for the online and offline comparisons, none a sandworm goes after
+    //so no random events can make the two runs differ
+    fn steady_items() -> Vec<Item> {
+        vec![
+            Item::new("Fremen", 40, 1.0, 100),
+            Item::new("Storm Shelter", 3, 90.0, 40000),
+            Item::new("Sietch", 2, 260.0, 130000).making_water(2.0),
+        ]
+    }
+
+    //Seeds the online and offline comparisons so they always check the same weather, prices and offers
+    const COMPARISON_SEED: u64 = 523;
+
+    //Copies a game the way saving and loading does, so both copies share every random seed
+    fn reload(game_state: &UserState) -> UserState {
+        let json = serde_json::to_string(game_state).unwrap();
+        let mut copy: UserState = serde_json::from_str(&json).unwrap();
+        copy.sync_catalog(steady_items(), vec![]);
+        copy
+    }
+
+    #[test]
+    fn test_online_matches_offline() {
+        let mut game_state = UserState::new(steady_items(), vec![]).with_seed(COMPARISON_SEED);
+        game_state.set_spice(100000.0);
+        assert!(game_state.start_research("Desert Survey"));
+        game_state.buffs.add(
+            Buff::multiply("Frenzy", BuffTarget::Sps, 7.0, 77.0),
+            Stacking::Refresh,
+        );
+        let price = game_state.get_market().get_price() * 1.02;
+        assert!(game_state.place_order(OrderSide::Sell, 1000.0, price));
+        game_state.calculate_sps();
+        let mut online = reload(&game_state);
+        let mut offline = reload(&game_state);
+
+        //Two hours of play at 4 frames a second, against the same time spent closed
+        //Chosen so it doesn't end right on a market tick or new offer
+        let mut clock = SimClock::default();
+        let mut steps = 0;
+        for _ in 0..28820 {
+            online.update(0.25);
+            steps += clock.advance(0.25);
+        }
+        offline.catch_up(steps as f64 * STEP);
+
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * a.abs().max(1.0);
+        assert!(close(online.get_spice(), offline.get_spice()));
+        assert!(close(online.get_water(), offline.get_water()));
+        assert!(close(online.get_solari(), offline.get_solari()));
+        assert_eq!(online.get_sps(), offline.get_sps());
+        assert_eq!(
+            online.get_weather().current(),
+            offline.get_weather().current()
+        );
+        assert_eq!(
+            online.get_market().get_price(),
+            offline.get_market().get_price()
+        );
+        assert_eq!(
+            online.get_market().get_orders().len(),
+            offline.get_market().get_orders().len()
+        );
+        assert!(online.research.is_done("Desert Survey"));
+        assert!(offline.research.is_done("Desert Survey"));
+        assert_eq!(online.amount_owned("Survey Camp"), 0);
+        assert_eq!(online.num_items(), offline.num_items());
+        assert_eq!(
+            online.contracts.get_offers().len(),
+            offline.contracts.get_offers().len()
+        );
+        assert_eq!(
+            online.contracts.get_offers()[0].describe(),
+            offline.contracts.get_offers()[0].describe()
+        );
+    }
+
+    #[test]
+    fn test_frame_rate_doesnt_matter() {
+        let game_state = UserState::new(steady_items(), vec![]).with_seed(COMPARISON_SEED);
+        let mut slow = reload(&game_state);
+        let mut fast = reload(&game_state);
+        for _ in 0..30000 {
+            slow.update(0.02);
+        }
+        for _ in 0..120000 {
+            fast.update(0.005);
+        }
+        //Same steps in the same order, so not even rounding differs
+        assert_eq!(slow.get_spice(), fast.get_spice());
+        assert_eq!(slow.get_water(), fast.get_water());
+        assert_eq!(
+            slow.get_weather().get_time_left(),
+            fast.get_weather().get_time_left()
+        );
+        //A frame long enough to be a stall is caught up instead of run step by step
+        let mut stalled = reload(&slow);
+        let mut closed = reload(&slow);
+        stalled.update(600.0);
+        closed.catch_up(600.0);
+        assert_eq!(stalled.get_spice(), closed.get_spice());
+    }
+
+    #[test]
+    fn test_sync_catalog() {
+        //Save from before Storm Shelters existed
+        let items = vec![
+            Item::new("Tools", 3, 0.1, 23),
+            Item::new("Sietch", 1, 260.0, 149500),
+        ];
+        //Thumpers were cheaper and weaker back then
+        let clicker_items = vec![ClickerItem::new("Thumper", 1.0, 2000).adding_sps(0.01)];
+        let mut game_state = UserState::new(items, clicker_items);
+        game_state.owned_clicker_items = vec![ClickerItem::new("CHOAM Charter", 2.0, 100)];
+        game_state.sync_catalog(
+            vec![
+                Item::new("Tools", 0, 0.1, 15),
+                Item::new("Storm Shelter", 0, 90.0, 40000),
+                Item::new("Sietch", 0, 260.0, 130000),
+            ],
+            vec![
+                ClickerItem::new("CHOAM Charter", 2.0, 100),
+                ClickerItem::new("Guild Satellite", 3.0, 500),
+                ClickerItem::new("Thumper", 1.5, 5000).adding_sps(0.02),
+            ],
+        );
+        let names: Vec<&str> = game_state.items.iter().map(|i| i.get_name()).collect();
+        assert_eq!(names, vec!["Tools", "Storm Shelter", "Sietch"]);
+        assert_eq!(game_state.amount_owned("Tools"), 3);
+        //Saved prices are ignored, the Sietch is priced from the catalog as the second one
+        assert_eq!(game_state.items[2].get_cost(), Cost::spice(149500));
+        //Charter is already owned so only the satellite joins the saved Thumper
+        assert_eq!(game_state.clicker_items.len(), 2);
+        //The saved Thumper takes the catalog's price and effects
+        let thumper = &game_state.clicker_items[0];
+        assert_eq!(thumper.get_name(), "Thumper");
+        assert_eq!(thumper.get_cost(), 5000);
+        assert_eq!(thumper.get_multiplier(), 1.5);
+        assert_eq!(thumper.get_sps_share(), 0.02);
+    }
+
+    #[test]
+    fn test_old_save_costs_recomputed() {
+        let mut game_state = UserState::new(vec![], vec![]);
+        game_state.faction = Some(Faction::Fremen);
+        //Older saves stored what each item made and its drifted cost
+        let mut json = serde_json::to_value(&game_state).unwrap();
+        json["items"] = serde_json::json!([
+            {"name": "Fremen", "amt": 2, "worth": 1.0, "cost": 133},
+            {"name": "Wind Trap", "amt": 1, "worth": 60.0, "cost": {"spice": 69001}},
+            {"name": "Removed Building", "amt": 3, "worth": 5.0, "cost": 10}
+        ]);
+        let mut loaded: UserState = serde_json::from_value(json).unwrap();
+        loaded.sync_catalog(vec![Item::new("Fremen", 0, 1.0, 100)], vec![]);
+        let names: Vec<&str> = loaded.items.iter().map(|i| i.get_name()).collect();
+        assert_eq!(names, vec!["Fremen", "Wind Trap"]);
+        assert_eq!(loaded.amount_owned("Fremen"), 2);
+        assert_eq!(loaded.items[0].get_cost(), Cost::spice(133));
+        assert_eq!(loaded.items[1].get_cost(), Cost::spice(69000));
+        assert_eq!(loaded.items[1].get_water(), 5.0);
+    }
+
+    #[test]
+    fn test_buffs_apply() {
+        let items = vec![Item::new("Fremen", 2, 1.0, 100)];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.buffs.add(
+            Buff::multiply("Frenzy", BuffTarget::Sps, 7.0, 10.0),
+            Stacking::Refresh,
+        );
+        game_state.buffs.add(
+            Buff::multiply("Click Frenzy", BuffTarget::Spc, 777.0, 10.0),
+            Stacking::Refresh,
+        );
+        game_state.buffs.add(
+            Buff {
+                op: crate::buffs::BuffOp::Add,
+                ..Buff::multiply(
+                    "Guild Bonus",
+                    BuffTarget::Building(String::from("Fremen")),
+                    1.0,
+                    10.0,
+                )
+            },
+            Stacking::Refresh,
+        );
+        game_state.calculate_sps();
+        //Fremen make (2 + 1) then the frenzy multiplies the total
+        assert_eq!(game_state.get_sps(), 21.0);
+        assert_eq!(game_state.update_spice_by_click(), 777.0);
+    }
+
+    #[test]
+    fn test_catch_up_splits_at_buff_expiry() {
+        let items = vec![Item::new("Fremen", 1, 1.0, 100)];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.buffs.add(
+            Buff::multiply("Frenzy", BuffTarget::Sps, 7.0, 10.0),
+            Stacking::Refresh,
+        );
+        //10 seconds at 7 sps then 90 seconds at 1 sps
+        game_state.catch_up(100.0);
+        assert_eq!(game_state.get_spice(), 160.0);
+        assert_eq!(game_state.get_sps(), 1.0);
+    }
+
+    #[test]
+    fn test_collect_spice_blow() {
+        let mut game_state = UserState::new(vec![], vec![]);
+        assert!(!game_state.collect_spice_blow());
+        game_state.spice_blows = SpiceBlows::new(2);
+        while game_state.get_spice_blow().is_none() {
+            game_state.update_spice(1.0);
+        }
+        assert!(game_state.collect_spice_blow());
+        assert!(game_state.get_spice_blow().is_none());
+        //Whatever the reward was, it left either spice or a buff behind
+        assert!(game_state.get_spice() > 0.0 || !game_state.get_buffs().get_active().is_empty());
+    }
+
+    #[test]
+    fn test_pledge() {
+        let items = vec![Item::new("Fremen", 1000, 1.0, 100)];
+        let mut game_state = UserState::new(items, vec![]);
+        assert!(!game_state.pledge(Faction::Fremen));
+        game_state.update_spice(1.0);
+        assert_eq!(game_state.get_sps(), 1000.0);
+        assert!(game_state.take_notices()[0].contains("allegiance"));
+        assert!(game_state.pledge(Faction::Fremen));
+        assert_eq!(game_state.get_faction(), Some(Faction::Fremen));
+        //Fremen double their own output
+        assert_eq!(game_state.get_sps(), 2000.0);
+        assert_eq!(game_state.amount_owned("Wind Trap"), 0);
+        assert_eq!(game_state.num_items(), 2);
+        assert_eq!(game_state.num_clicker_items(), 1);
+        //Can't switch sides without prestiging
+        assert!(!game_state.pledge(Faction::Harkonnen));
+    }
+
+    #[test]
+    fn test_prestige_resets_faction() {
+        let items = vec![Item::new("Fremen", 1000, 1.0, 100)];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.calculate_sps();
+        assert!(game_state.pledge(Faction::BeneGesserit));
+        assert_eq!(game_state.get_spc(), 3.0);
+        assert!(!game_state.prestige(vec![], vec![]));
+        game_state.set_spice(PRESTIGE_SPICE);
+        let items = vec![Item::new("Fremen", 10, 1.0, 100)];
+        assert!(game_state.prestige(items, vec![]));
+        assert_eq!(game_state.get_faction(), None);
+        assert_eq!(game_state.get_spice(), 0.0);
+        assert_eq!(game_state.get_spc(), 1.0);
+        //10 Fremen with the 10% prestige bonus
+        assert_eq!(game_state.get_sps(), 11.0);
+    }
+
+    #[test]
+    fn test_research_unlocks() {
+        let mut game_state = UserState::new(vec![], vec![]);
+        game_state.set_spice(100000.0);
+        assert!(!game_state.start_research("CHOAM Futures"));
+        assert!(!game_state.start_research("Time Travel"));
+        assert!(game_state.start_research("desert survey"));
+        assert!(!game_state.start_research("Stillsuit Design"));
+        assert_eq!(game_state.get_spice(), 80000.0);
+        //Research keeps going offline
+        game_state.catch_up(120.0);
+        assert_eq!(game_state.amount_owned("Survey Camp"), 0);
+        assert_eq!(game_state.num_items(), 1);
+        assert!(!game_state.is_unlocked(catalog::LIMIT_ORDERS));
+        assert!(game_state.start_research("CHOAM Futures"));
+        game_state.update_spice(180.0);
+        assert!(game_state.is_unlocked(catalog::LIMIT_ORDERS));
+        assert!(game_state.list_research().contains("[Done] CHOAM Futures"));
+        //Prestiging keeps research and what it unlocked
+        game_state.set_spice(PRESTIGE_SPICE);
+        assert!(game_state.prestige(vec![], vec![]));
+        assert_eq!(game_state.num_items(), 1);
+    }
+
+    #[test]
+    fn test_contracts() {
+        let items = vec![Item::new("Fremen", 2, 1.0, 100)];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.contracts = Contracts::new(4);
+        game_state.update_spice(1.0);
+        assert_eq!(game_state.contracts.get_offers().len(), 1);
+        assert!(!game_state.accept_contract(1));
+        assert!(game_state.accept_contract(0));
+        //Accepted contracts come first with their progress
+        let listed = game_state.list_contracts();
+        assert!(listed.lines().nth(1).unwrap().starts_with('['));
+        assert!(listed.contains("Offer") || listed.contains("No offers"));
+        //Whatever it asked for, a big pile of spice, buildings and clicks covers it
+        game_state.set_spice(1_000_000.0);
+        game_state.items[0] = Item::new("Fremen", 100, 1.0, 100);
+        game_state.total_clicks = 1000;
+        game_state.take_notices();
+        game_state.update_spice(1.0);
+        assert!(game_state.contracts.get_active().is_empty());
+        assert!(
+            game_state
+                .take_notices()
+                .iter()
+                .any(|n| n.starts_with("Contract complete"))
+        );
+    }
+
+    #[test]
+    fn test_contract_failure_penalty() {
+        let mut game_state = UserState::new(vec![], vec![]);
+        game_state.contracts = Contracts::new(4);
+        game_state.update_spice(1.0);
+        assert!(game_state.accept_contract(0));
+        let penalty = game_state.contracts.get_active()[0].penalty;
+        game_state.set_spice(penalty / 2.0);
+        //Left alone for longer than any contract lasts, and it never gets done
+        game_state.catch_up(1000.0);
+        assert!(game_state.contracts.get_active().is_empty());
+        //Can't lose more than what's there
+        assert_eq!(game_state.get_spice(), 0.0);
+    }
+
+    #[test]
+    fn test_click_sps_share_and_combo() {
+        let items = vec![Item::new("Spice Harvester", 10, 100.0, 12000)];
+        let clicker_items = vec![ClickerItem::new("Thumper", 1.0, 5000).adding_sps(0.01)];
+        let mut game_state = UserState::new(items, clicker_items);
+        game_state.calculate_sps();
+        let base = game_state.get_spc();
+        game_state.set_spice(5000.0);
+        game_state.buy_clicker_item(0);
+        //1% of the harvesters' 1000 sps goes on top of every click
+        assert!((game_state.get_spc() - (base + 0.01 * game_state.get_sps())).abs() < 1e-9);
+
+        //Clicking fast for a while builds a combo that falls off once clicking stops
+        for _ in 0..40 {
+            game_state.update_spice(0.25);
+            game_state.update_spice_by_click();
+        }
+        assert!(game_state.get_combo() > 1.0);
+        let spc = game_state.get_spc();
+        let click = game_state.update_spice_by_click();
+        assert!((click - spc * game_state.get_combo()).abs() < 1e-6);
+        game_state.update_spice(60.0);
+        assert_eq!(game_state.get_combo(), 1.0);
+    }
+
+    #[test]
+    fn test_buy_items_bulk() {
+        let items = vec![
+            Item::new("Carryall", 0, 120.0, 100).with_curve(CostCurve::Stepwise {
+                tier_size: 5,
+                multiplier: 2.0,
+            }),
+        ];
+        let mut game_state = UserState::new(items, vec![]);
+        //5 at 100 then 5 at 200
+        game_state.set_spice(1499.0);
+        game_state.buy_items(0, 10);
+        assert_eq!(game_state.amount_owned("Carryall"), 0);
+        assert!(
+            game_state
+                .shop_tooltip(0)
+                .unwrap()
+                .contains("Next 10 cost 1500")
+        );
+        game_state.set_spice(1500.0);
+        assert!(game_state.buy_shop_entries(0, 10));
+        assert_eq!(game_state.amount_owned("Carryall"), 10);
+        assert_eq!(game_state.get_spice(), 0.0);
+        assert_eq!(game_state.items[0].get_cost(), Cost::spice(400));
+        //Huge counts are turned down instead of priced one by one
+        game_state.set_spice(f64::MAX);
+        assert!(game_state.buy_shop_entries(0, 4_000_000_000));
+        assert_eq!(game_state.amount_owned("Carryall"), 10);
+        assert_eq!(game_state.get_spice(), f64::MAX);
+    }
+
+    #[test]
+    fn test_click_limits() {
+        let mut game_state = UserState::new(vec![], vec![]);
+        game_state.set_click_limits(ClickLimits {
+            max_per_second: Some(10),
+            detect_regular: true,
+        });
+        //An autoclicker going 100 times a second for 2 seconds, perfectly steady
+        let mut credited = 0;
+        for _ in 0..200 {
+            game_state.update_spice(0.01);
+            if game_state.update_spice_by_click() > 0.0 {
+                credited += 1;
+            }
+        }
+        assert!((19..=21).contains(&credited));
+        assert_eq!(game_state.total_clicks, credited);
+        assert!(!game_state.is_leaderboard_eligible());
+        assert!(game_state.list_stats().contains("Leaderboard eligible: No"));
+        assert!(
+            game_state
+                .take_notices()
+                .iter()
+                .any(|n| n.starts_with("Clicking looks automated"))
+        );
+    }
+
+    #[test]
+    fn test_shop_reveal() {
+        let items = vec![
+            Item::new("Tools", 0, 0.1, 15),
+            Item::new("Fremen", 0, 1.0, 100).revealed_when(Reveal::Spice(0.5)),
+            Item::new("Deathstill", 0, 0.0, 3000).revealed_when(Reveal::Owns("Fremen", 1)),
+        ];
+        let clicker_items =
+            vec![ClickerItem::new("CHOAM Charter", 2.0, 100).revealed_when(Reveal::Spice(0.5))];
+        let mut game_state = UserState::new(items, clicker_items);
+        assert_eq!(game_state.shop_entries()[1], "???");
+        assert_eq!(game_state.shop_entries()[3], "???");
+        assert!(game_state.shop_tooltip(2).unwrap().contains("Own 1 Fremen"));
+        //Can't buy what hasn't been revealed, even with the spice for it
+        game_state.set_spice(5000.0);
+        game_state.buy_item(1);
+        assert_eq!(game_state.get_spice(), 5000.0);
+
+        //Spending spice doesn't hide things again once lifetime spice got there
+        game_state.set_spice(0.0);
+        for _ in 0..50 {
+            game_state.update_spice_by_click();
+        }
+        game_state.update_spice(0.001);
+        let notices = game_state.take_notices();
+        assert!(notices.contains(&String::from("New in the shop: Fremen")));
+        assert!(notices.contains(&String::from("New in the shop: CHOAM Charter")));
+        game_state.set_spice(100.0);
+        game_state.buy_item(1);
+        assert_eq!(game_state.amount_owned("Fremen"), 1);
+        assert!(game_state.shop_entries()[2].starts_with("Deathstill"));
+        assert!(
+            game_state
+                .take_notices()
+                .contains(&String::from("New in the shop: Deathstill"))
+        );
+    }
+
+    #[test]
+    fn test_synergies() {
+        let items = vec![
+            Item::new("Fremen", 10, 1.0, 100),
+            Item::new("Sietch", 5, 260.0, 130000),
+        ];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.calculate_sps();
+        //5 Sietches make Fremen 5% better
+        assert_eq!(game_state.get_sps(), 10.5 + 1300.0);
+        let inventory = game_state.list_inventory();
+        assert!(inventory.contains("Output: 10.00 base + 0.50 synergy (+5.0%)"));
+        assert!(inventory.contains("Output: 1300.00 base + 0.00 synergy (+0.0%)"));
+    }
+
+    #[test]
+    fn test_advisor() {
+        let items = vec![
+            Item::new("Tools", 0, 0.05, 20),
+            Item::new("Fremen", 10, 1.0, 100),
+            Item::new("Ornithopter", 0, 12.0, 1100),
+        ];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.calculate_sps();
+        //At 10 sps an Ornithopter takes 110 seconds to afford but earns its price back in 92, beating the rest
+        assert_eq!(game_state.best_value(), Some(2));
+        let entries = game_state.shop_entries();
+        assert!(entries[0].contains("400 spice per sps, pays back in 6m 42s"));
+        assert!(entries[2].ends_with("(best value)"));
+        assert!(!entries[1].contains("(best value)"));
+
+        //The advisor and the simulator's payback bot pick the same way
+        let choices = game_state.purchase_choices();
+        let pick = Strategy::Payback.choose(&choices, game_state.get_spice(), game_state.get_sps());
+        assert_eq!(pick.map(|choice| choice.index), Some(2));
+
+        //Each planned Ornithopter makes the next one cost more
+        let advice = game_state.advise(3);
+        assert_eq!(advice.len(), 3);
+        assert!(
+            advice
+                .iter()
+                .all(|(choice, _)| choice.name == "Ornithopter")
+        );
+        assert!(advice[0].0.cost < advice[1].0.cost && advice[1].0.cost < advice[2].0.cost);
+        assert!(game_state.list_advice(3).contains(
+            "1. Ornithopter for 1100 spice, +12.00 spice per second, pays back in 3m 22s"
+        ));
+        //Planning doesn't buy anything
+        assert_eq!(game_state.amount_owned("Ornithopter"), 0);
+
+        //Water only covers one Sietch, so only one gets planned even though it's the best value
+        let items = vec![
+            Item::new("Fremen", 10, 1.0, 100),
+            Item::with_cost(
+                "Sietch",
+                0,
+                50.0,
+                Cost {
+                    spice: 100,
+                    water: 100,
+                    solari: 0,
+                },
+            ),
+        ];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.water = 150.0;
+        game_state.calculate_sps();
+        let advice = game_state.advise(3);
+        let sietches = advice
+            .iter()
+            .filter(|(choice, _)| choice.name == "Sietch")
+            .count();
+        assert_eq!(sietches, 1);
+        assert_eq!(advice[0].0.name, "Sietch");
+    }
+
+    #[test]
+    fn test_goal() {
+        let items = vec![Item::new("Fremen", 10, 1.0, 100)];
+        let mut game_state = UserState::new(items, vec![]);
+        game_state.set_spice(100.0);
+        assert!(!game_state.set_goal(50.0));
+        assert!(game_state.set_goal(400.0));
+        game_state.calculate_sps();
+        assert_eq!(game_state.goal_progress(), Some(0.25));
+        //300 more at 10 a second
+        assert_eq!(game_state.goal_eta(), Some(30.0));
+        assert_eq!(
+            game_state.describe_goal(),
+            "Goal: 400 spice, 25% there, about 30s to go"
+        );
+
+        //The goal is saved with the game
+        let json = serde_json::to_string(&game_state).unwrap();
+        let mut game_state: UserState = serde_json::from_str(&json).unwrap();
+        game_state.sync_catalog(vec![Item::new("Fremen", 0, 1.0, 100)], vec![]);
+        assert_eq!(game_state.get_goal(), Some(400.0));
+
+        game_state.update(29.0);
+        assert!(!game_state.take_goal_reached());
+        game_state.update(1.5);
+        assert!(game_state.take_goal_reached());
+        assert!(!game_state.take_goal_reached());
+        assert_eq!(game_state.get_goal(), None);
+        assert!(
+            game_state
+                .take_notices()
+                .contains(&String::from("Goal reached! You have 400 spice"))
+        );
+
+        //Time away counts too
+        assert!(game_state.set_goal(10000.0));
+        game_state.catch_up(3600.0);
+        assert!(game_state.take_goal_reached());
+        assert!(!game_state.clear_goal());
+    }
+
     #[test]
     fn test_time_update() {
         let items = vec![];
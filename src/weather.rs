@@ -0,0 +1,164 @@
+use crate::buffs::{Buff, BuffTarget};
+use crate::rng::Rng;
+use serde::{Deserialize, Serialize};
+
+//Shortest and longest calm spell, in seconds
+const MIN_CALM: f64 = 300.0;
+const MAX_CALM: f64 = 900.0;
+//Shortest and longest Coriolis storm, in seconds
+const MIN_STORM: f64 = 60.0;
+const MAX_STORM: f64 = 180.0;
+
+//Weather over the spice fields
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Weather {
+    Calm,
+    CoriolisStorm,
+}
+
+impl Weather {
+    //Name shown in the HUD
+    pub fn name(&self) -> &'static str {
+        match self {
+            Weather::Calm => "Calm",
+            Weather::CoriolisStorm => "Coriolis Storm",
+        }
+    }
+
+    //Building multipliers while this weather lasts, as (buff name, building, multiplier)
+    fn effects(&self) -> &'static [(&'static str, &'static str, f64)] {
+        match self {
+            Weather::Calm => &[
+                ("Clear Skies", "Ornithopter", 1.25),
+                ("Calm Sands", "Spice Harvester", 1.1),
+            ],
+            Weather::CoriolisStorm => &[
+                ("Grounded Ornithopters", "Ornithopter", 0.5),
+                ("Buried Harvesters", "Spice Harvester", 0.75),
+                ("Sheltered Crews", "Storm Shelter", 3.0),
+            ],
+        }
+    }
+
+    //Gets the buffs for this weather lasting the given number of seconds
+    pub fn buffs(&self, duration: f64) -> Vec<Buff> {
+        self.effects()
+            .iter()
+            .map(|(name, building, multiplier)| {
+                Buff::multiply(
+                    name,
+                    BuffTarget::Building(building.to_string()),
+                    *multiplier,
+                    duration,
+                )
+            })
+            .collect()
+    }
+}
+
+//Alternating calm spells and storms, the whole schedule comes from the seed
+//Each phase's length is worked out from the seed and its number, so nothing else needs saving
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct WeatherCycle {
+    seed: u64,
+    //How many phases have passed, even phases are calm and odd are storms
+    phase: u64,
+    //Seconds left in the current phase
+    time_left: f64,
+}
+
+impl Default for WeatherCycle {
+    fn default() -> WeatherCycle {
+        WeatherCycle::new(Rng::from_time().next_u64())
+    }
+}
+
+impl WeatherCycle {
+    //Create a weather cycle from a seed, starting with a calm spell
+    pub fn new(seed: u64) -> WeatherCycle {
+        WeatherCycle {
+            seed,
+            phase: 0,
+            time_left: phase_length(seed, 0),
+        }
+    }
+
+    //Gets the current weather
+    pub fn current(&self) -> Weather {
+        if self.phase.is_multiple_of(2) {
+            Weather::Calm
+        } else {
+            Weather::CoriolisStorm
+        }
+    }
+
+    //Gets the seconds left until the weather changes
+    pub fn get_time_left(&self) -> f64 {
+        self.time_left
+    }
+
+    //Moves time forward, returns how many times the weather changed
+    pub fn advance(&mut self, dt: f64) -> u32 {
+        let mut changes = 0;
+        self.time_left -= dt;
+        while self.time_left <= 0.0 {
+            self.phase += 1;
+            self.time_left += phase_length(self.seed, self.phase);
+            changes += 1;
+        }
+        changes
+    }
+}
+
+//Gets the length of one phase of the schedule
+fn phase_length(seed: u64, phase: u64) -> f64 {
+    let mut rng = Rng::new(seed ^ phase.wrapping_mul(0x2545F4914F6CDD1D));
+    if phase.is_multiple_of(2) {
+        rng.range(MIN_CALM, MAX_CALM)
+    } else {
+        rng.range(MIN_STORM, MAX_STORM)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alternates() {
+        let mut weather = WeatherCycle::new(8);
+        assert_eq!(weather.current(), Weather::Calm);
+        let calm = weather.get_time_left();
+        assert!((MIN_CALM..MAX_CALM).contains(&calm));
+        assert_eq!(weather.advance(calm), 1);
+        assert_eq!(weather.current(), Weather::CoriolisStorm);
+        assert!((MIN_STORM..MAX_STORM).contains(&weather.get_time_left()));
+    }
+
+    #[test]
+    fn test_one_big_step_matches_small_steps() {
+        //Offline progress takes one big step, playing takes lots of small ones
+        let mut offline = WeatherCycle::new(21);
+        let mut online = WeatherCycle::new(21);
+        let big_changes = offline.advance(36000.0);
+        let mut small_changes = 0;
+        for _ in 0..36000 {
+            small_changes += online.advance(1.0);
+        }
+        assert_eq!(big_changes, small_changes);
+        assert_eq!(offline.current(), online.current());
+        assert!((offline.get_time_left() - online.get_time_left()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_storm_buffs() {
+        let buffs = Weather::CoriolisStorm.buffs(100.0);
+        assert_eq!(buffs.len(), 3);
+        assert!(buffs[0].is_debuff());
+        assert!(!buffs[2].is_debuff());
+        assert_eq!(
+            buffs[2].target,
+            BuffTarget::Building(String::from("Storm Shelter"))
+        );
+    }
+}